@@ -1,52 +1,194 @@
-#[allow(dead_code)]
+use std::collections::BTreeSet;
+
+use crate::{
+    analysis::{Analysis, MonotoneFramework},
+    expression::Label,
+    program::Program,
+};
+
+/// the algorithms computing the solution of an instance of the monotone framework
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Solver {
+    /// maximal fixed point, using a worklist
+    Mfp,
+    /// chaotic iteration, updating all labels at once
+    Chaotic,
+    /// round-robin iteration, updating one label at a time
+    RoundRobin,
+}
+impl Solver {
+    /// solves an analysis using the algorithm
+    pub fn run<F: MonotoneFramework>(
+        self,
+        program: &Program,
+        framework: &F,
+    ) -> Analysis<F::Lattice> {
+        match self {
+            Self::Mfp => mfp::run(program, framework),
+            Self::Chaotic => chaotic_iter::run(program, framework),
+            Self::RoundRobin => round_robin::run(program, framework),
+        }
+    }
+}
+
+/// A∘(l) = ⊔{f_(l',l)(A•(l')) | (l', l) in F} ⊔ ι_E(l)
+fn context_at<F: MonotoneFramework>(
+    framework: &F,
+    flow: &BTreeSet<(Label, Label)>,
+    ext_lab: &BTreeSet<Label>,
+    effect: impl Fn(Label) -> F::Lattice,
+    label: Label,
+) -> F::Lattice {
+    let init = if ext_lab.contains(&label) {
+        framework.extremal_value()
+    } else {
+        framework.bottom()
+    };
+
+    flow.iter()
+        .filter(|(_, l)| l == &label)
+        .fold(init, |acc, (l_prime, l)| {
+            let value = framework.transfer_edge((*l_prime, *l), &effect(*l_prime));
+            framework.join(&acc, &value)
+        })
+}
+
+/// chaotic iteration, recomputing A∘ at every label at once until nothing changes
 pub mod chaotic_iter {
+    use std::collections::BTreeMap;
+
     use crate::{
-        analysis::{lv_entry, lv_exit, LVAnalysis},
+        analysis::{Analysis, MonotoneFramework},
+        expression::Label,
         program::Program,
     };
 
-    pub fn run(program: &Program) -> LVAnalysis {
-        let mut lva: LVAnalysis = LVAnalysis::new(program.len);
+    pub fn run<F: MonotoneFramework>(program: &Program, framework: &F) -> Analysis<F::Lattice> {
+        let flow = framework.flow(program);
+        let ext_lab = framework.extremal_labels(program);
+        let heads = program.loop_heads();
+        let f_l = |a: &F::Lattice, l: Label| framework.transfer(&program.at(l).unwrap(), a);
+
+        let mut context: BTreeMap<Label, F::Lattice> = (1..=program.len)
+            .map(|label| (label, framework.bottom()))
+            .collect();
+        let mut effect = context.clone();
 
         loop {
-            let lva_next = LVAnalysis {
-                exit: lv_exit(program, &lva.entry),
-                entry: lv_entry(program, &lva.exit),
-            };
+            let context_next: BTreeMap<Label, F::Lattice> = (1..=program.len)
+                .map(|label| {
+                    let value = super::context_at(
+                        framework,
+                        &flow,
+                        &ext_lab,
+                        |l| effect[&l].clone(),
+                        label,
+                    );
+
+                    if heads.contains(&label) {
+                        (label, framework.widen(&context[&label], &value))
+                    } else {
+                        (label, value)
+                    }
+                })
+                .collect();
+
+            // A•(l) = f_l(A∘(l))
+            let effect_next: BTreeMap<Label, F::Lattice> = (1..=program.len)
+                .map(|label| (label, f_l(&context[&label], label)))
+                .collect();
 
-            if lva_next == lva {
+            if context_next == context && effect_next == effect {
                 break;
             }
 
-            lva = lva_next;
+            context = context_next;
+            effect = effect_next;
         }
 
-        lva
+        let context = super::narrowing::run(program, framework, context);
+        let effect = (1..=program.len)
+            .map(|label| (label, f_l(&context[&label], label)))
+            .collect();
+
+        Analysis::new(framework, program, context, effect)
     }
 }
 
-pub mod mfp {
+/// round-robin iteration, recomputing A∘ at one label at a time
+pub mod round_robin {
+    use std::collections::BTreeMap;
+
     use crate::{
-        analysis::{lv_entry, lv_entry_at, LVAnalysis, LVExit},
-        expression::{Label, Variable},
+        analysis::{Analysis, Direction, MonotoneFramework},
+        expression::Label,
         program::Program,
     };
-    use std::collections::HashSet;
 
-    type Lattice = HashSet<Variable>;
-    type Analysis = LVExit;
+    /// visits the labels in a fixed order (ascending for forward analyses, descending for
+    /// backward ones), updating A∘(l) in place, until a full round changes nothing
+    pub fn run<F: MonotoneFramework>(program: &Program, framework: &F) -> Analysis<F::Lattice> {
+        let flow = framework.flow(program);
+        let ext_lab = framework.extremal_labels(program);
+        let heads = program.loop_heads();
+        let f_l = |a: &F::Lattice, l: Label| framework.transfer(&program.at(l).unwrap(), a);
+
+        let order: Vec<Label> = match framework.direction() {
+            Direction::Forward => (1..=program.len).collect(),
+            Direction::Backward => (1..=program.len).rev().collect(),
+        };
+
+        let mut context: BTreeMap<Label, F::Lattice> = (1..=program.len)
+            .map(|label| (label, framework.bottom()))
+            .collect();
+
+        let mut change = true;
+        while change {
+            change = false;
+
+            for &label in &order {
+                let effect = |l: Label| f_l(&context[&l], l);
+                let mut value = super::context_at(framework, &flow, &ext_lab, effect, label);
+                if heads.contains(&label) {
+                    value = framework.widen(&context[&label], &value);
+                }
+
+                if value != context[&label] {
+                    context.insert(label, value);
+                    change = true;
+                }
+            }
+        }
+
+        let context = super::narrowing::run(program, framework, context);
+        let effect = (1..=program.len)
+            .map(|label| (label, f_l(&context[&label], label)))
+            .collect();
+
+        Analysis::new(framework, program, context, effect)
+    }
+}
 
-    pub fn run(program: &Program) -> LVAnalysis {
-        let bottom: Lattice = [].into();
-        let ext_lab: HashSet<Label> = [program.init_label()].into();
-        let ext_val: Lattice = [].into();
-        let flow = program.flow_r();
-        let f_l = |p: &Program, a: &LVExit| lv_entry(p, a);
-        let f_l_at = |p: &Program, a: &LVExit, l: Label| lv_entry_at(p, a, l);
+/// the maximal fixed point solution, computed using a worklist of edges
+pub mod mfp {
+    use crate::{
+        analysis::{Analysis, MonotoneFramework},
+        expression::Label,
+        program::Program,
+    };
+    use std::collections::BTreeMap;
+
+    pub fn run<F: MonotoneFramework>(program: &Program, framework: &F) -> Analysis<F::Lattice> {
+        let bottom = framework.bottom();
+        let ext_lab = framework.extremal_labels(program);
+        let ext_val = framework.extremal_value();
+        let flow = framework.flow(program);
+        let heads = program.loop_heads();
+        let f_l = |a: &F::Lattice, l: Label| framework.transfer(&program.at(l).unwrap(), a);
 
         // step 1: initialize
         let mut work_list: Vec<(Label, Label)> = flow.iter().cloned().collect();
-        let mut ana: Analysis = (1..=program.len)
+        let mut ana: BTreeMap<Label, F::Lattice> = (1..=program.len)
             .map(|label| {
                 (
                     label,
@@ -59,22 +201,352 @@ pub mod mfp {
             })
             .collect();
 
-        // step 2: iterate
-        while work_list.len() > 0 {
+        // step 2: iterate, widening at loop heads
+        while !work_list.is_empty() {
             let (l, l_p) = work_list.remove(0);
 
-            let (a, b) = (f_l_at(program, &ana, l), &ana[&l_p]);
-            if !a.is_subset(&b) {
-                ana.insert(l_p, a.union(b).cloned().collect());
+            let (a, b) = (
+                framework.transfer_edge((l, l_p), &f_l(&ana[&l], l)),
+                &ana[&l_p],
+            );
+            if !framework.leq(&a, b) {
+                let joined = framework.join(b, &a);
+                if heads.contains(&l_p) {
+                    ana.insert(l_p, framework.widen(b, &joined));
+                } else {
+                    ana.insert(l_p, joined);
+                }
 
                 work_list.extend(flow.iter().filter(|(l_p_2, _)| &l_p == l_p_2))
             }
         }
 
-        // step 3: present
-        LVAnalysis {
-            exit: ana.clone(),
-            entry: f_l(program, &ana),
+        // step 3: narrow the result of widening
+        let ana = super::narrowing::run(program, framework, ana);
+
+        // step 4: present
+        let effect = (1..=program.len)
+            .map(|label| (label, f_l(&ana[&label], label)))
+            .collect();
+
+        Analysis::new(framework, program, ana, effect)
+    }
+}
+
+/// context-sensitive analysis of programs with procedures, keeping the values for calls with
+/// different call strings of length at most k apart
+pub mod call_strings {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::{
+        analysis::{CallString, ContextAnalysis, Direction, MonotoneFramework},
+        expression::Label,
+        program::Program,
+    };
+
+    /// the values at a label for each call string it is reached with
+    type Contexts<L> = BTreeMap<CallString, L>;
+
+    /// ⌈l_c : δ⌉_k, the call string δ extended by the call l_c and truncated to its k most
+    /// recent calls
+    fn push(call: Label, context: &CallString, depth: usize) -> CallString {
+        [call].iter().chain(context).take(depth).cloned().collect()
+    }
+
+    /// how the values are propagated along an edge of the flow
+    #[derive(Clone, Copy, Debug)]
+    enum Edge {
+        /// within a procedure or the main program, in the same context
+        Local,
+        /// from a call into the called procedure, pushing the call onto the call string
+        Enter(Label),
+        /// from a procedure back to the caller: the values in context ⌈l_c : δ⌉_k flow to
+        /// context δ, for every δ the caller-side label is reached with
+        Leave(Label, Label),
+    }
+
+    struct Solver<'a, F: MonotoneFramework> {
+        program: &'a Program,
+        framework: &'a F,
+        depth: usize,
+        flow: BTreeSet<(Label, Label)>,
+        edges: BTreeMap<(Label, Label), Edge>,
+    }
+    impl<F: MonotoneFramework> Solver<'_, F> {
+        fn f_l(&self, state: &F::Lattice, label: Label) -> F::Lattice {
+            let block = self.program.at(label).unwrap();
+            self.framework.transfer(&block, state)
+        }
+
+        /// the values propagated along the edge (l, l'), for each context of l'
+        fn propagate(
+            &self,
+            (l, l_p): (Label, Label),
+            ana: &BTreeMap<Label, Contexts<F::Lattice>>,
+        ) -> Contexts<F::Lattice> {
+            let value =
+                |state: &F::Lattice| self.framework.transfer_edge((l, l_p), &self.f_l(state, l));
+
+            match self.edges.get(&(l, l_p)).copied().unwrap_or(Edge::Local) {
+                Edge::Local => ana[&l]
+                    .iter()
+                    .map(|(context, state)| (context.clone(), value(state)))
+                    .collect(),
+                Edge::Enter(call) => ana[&l]
+                    .iter()
+                    .map(|(context, state)| (push(call, context, self.depth), value(state)))
+                    .collect(),
+                Edge::Leave(call, caller) => ana[&caller]
+                    .keys()
+                    .filter_map(|context| {
+                        let state = ana[&l].get(&push(call, context, self.depth))?;
+                        Some((context.clone(), value(state)))
+                    })
+                    .collect(),
+            }
         }
     }
+
+    pub fn run<F: MonotoneFramework>(
+        program: &Program,
+        framework: &F,
+        depth: usize,
+    ) -> ContextAnalysis<F::Lattice> {
+        let ext_lab = framework.extremal_labels(program);
+        let heads = program.loop_heads();
+
+        // a procedure is entered along (l_c, l_n) and left along (l_x, l_r) in forward
+        // analyses, and entered along (l_r, l_x) and left along (l_n, l_c) in backward ones
+        let mut edges = BTreeMap::new();
+        for (l_c, l_n, l_x, l_r) in program.inter_flow() {
+            match framework.direction() {
+                Direction::Forward => {
+                    edges.insert((l_c, l_n), Edge::Enter(l_c));
+                    edges.insert((l_x, l_r), Edge::Leave(l_c, l_c));
+                }
+                Direction::Backward => {
+                    edges.insert((l_r, l_x), Edge::Enter(l_c));
+                    edges.insert((l_n, l_c), Edge::Leave(l_c, l_r));
+                }
+            }
+        }
+
+        let solver = Solver {
+            program,
+            framework,
+            depth,
+            flow: framework.flow(program),
+            edges,
+        };
+
+        // step 1: initialize, with the extremal value in the empty context
+        let mut work_list: Vec<(Label, Label)> = solver.flow.iter().cloned().collect();
+        let mut ana: BTreeMap<Label, Contexts<F::Lattice>> = (1..=program.len)
+            .map(|label| {
+                let mut contexts = Contexts::new();
+                if ext_lab.contains(&label) {
+                    contexts.insert(vec![], framework.extremal_value());
+                }
+                (label, contexts)
+            })
+            .collect();
+
+        // step 2: iterate, widening at loop heads
+        while !work_list.is_empty() {
+            let (l, l_p) = work_list.remove(0);
+
+            let mut change = false;
+            for (context, a) in solver.propagate((l, l_p), &ana) {
+                let b = ana[&l_p].get(&context).cloned();
+                let b = b.unwrap_or_else(|| framework.bottom());
+                if !framework.leq(&a, &b) {
+                    let joined = framework.join(&b, &a);
+                    let value = if heads.contains(&l_p) {
+                        framework.widen(&b, &joined)
+                    } else {
+                        joined
+                    };
+                    ana.get_mut(&l_p).unwrap().insert(context, value);
+                    change = true;
+                }
+            }
+
+            if change {
+                // new contexts at a caller-side label are also matched when leaving the
+                // procedure
+                work_list.extend(solver.flow.iter().filter(|edge| {
+                    edge.0 == l_p
+                        || matches!(solver.edges.get(edge), Some(Edge::Leave(_, caller)) if *caller == l_p)
+                }));
+            }
+        }
+
+        // step 3: narrow the result of widening, recomputing every context at once
+        loop {
+            let mut ana_next: BTreeMap<Label, Contexts<F::Lattice>> = (1..=program.len)
+                .map(|label| (label, Contexts::new()))
+                .collect();
+            for label in &ext_lab {
+                ana_next
+                    .get_mut(label)
+                    .unwrap()
+                    .insert(vec![], framework.extremal_value());
+            }
+            for &(l, l_p) in &solver.flow {
+                for (context, a) in solver.propagate((l, l_p), &ana) {
+                    let contexts = ana_next.get_mut(&l_p).unwrap();
+                    let b = contexts.remove(&context);
+                    let b = b.unwrap_or_else(|| framework.bottom());
+                    contexts.insert(context, framework.join(&b, &a));
+                }
+            }
+            for label in &heads {
+                for (context, value) in ana_next.get_mut(label).unwrap() {
+                    if let Some(previous) = ana[label].get(context) {
+                        *value = framework.narrow(previous, value);
+                    }
+                }
+            }
+
+            if ana_next == ana {
+                break;
+            }
+            ana = ana_next;
+        }
+
+        // step 4: present
+        let effect = ana
+            .iter()
+            .map(|(label, contexts)| {
+                let contexts = contexts
+                    .iter()
+                    .map(|(context, state)| (context.clone(), solver.f_l(state, *label)))
+                    .collect();
+                (*label, contexts)
+            })
+            .collect();
+
+        match framework.direction() {
+            Direction::Forward => ContextAnalysis {
+                depth,
+                entry: ana,
+                exit: effect,
+            },
+            Direction::Backward => ContextAnalysis {
+                depth,
+                entry: effect,
+                exit: ana,
+            },
+        }
+    }
+}
+
+/// descending iteration after widening
+pub mod narrowing {
+    use std::collections::BTreeMap;
+
+    use crate::{analysis::MonotoneFramework, expression::Label, program::Program};
+
+    /// improves a solution A∘ obtained using widening by iterating downwards from it, applying
+    /// narrowing at loop heads
+    pub fn run<F: MonotoneFramework>(
+        program: &Program,
+        framework: &F,
+        context: BTreeMap<Label, F::Lattice>,
+    ) -> BTreeMap<Label, F::Lattice> {
+        let flow = framework.flow(program);
+        let ext_lab = framework.extremal_labels(program);
+        let heads = program.loop_heads();
+
+        let mut context = context;
+        loop {
+            let effect: BTreeMap<Label, F::Lattice> = (1..=program.len)
+                .map(|label| {
+                    let block = program.at(label).unwrap();
+                    (label, framework.transfer(&block, &context[&label]))
+                })
+                .collect();
+
+            let context_next: BTreeMap<Label, F::Lattice> = (1..=program.len)
+                .map(|label| {
+                    let value = super::context_at(
+                        framework,
+                        &flow,
+                        &ext_lab,
+                        |l| effect[&l].clone(),
+                        label,
+                    );
+
+                    if heads.contains(&label) {
+                        (label, framework.narrow(&context[&label], &value))
+                    } else {
+                        (label, value)
+                    }
+                })
+                .collect();
+
+            if context_next == context {
+                return context;
+            }
+
+            context = context_next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::{
+        analysis::{
+            ds::{DetectionOfSigns, Sign},
+            CallString,
+        },
+        expression::Variable,
+        parser,
+    };
+
+    const FIB: &str = "begin
+        proc fib(val z, u, res v) is
+            if z < 3 then v := u + 1
+            else call fib(z - 1, u, v); call fib(z - 2, v, v) endif
+        end;
+        call fib(x, 0, y)
+    end ";
+
+    #[test]
+    fn call_strings_distinguish_the_recursive_calls() {
+        let program = parser::parse(FIB).unwrap();
+        let ds = DetectionOfSigns::new(&program);
+        let u = Variable::new("u");
+
+        // at the entry of fib (label 1), for the calls at labels 4, 6 and 9
+        let k1 = call_strings::run(&program, &ds, 1);
+        let contexts: BTreeSet<&CallString> = k1.entry[&1].keys().collect();
+        assert_eq!(contexts, [&vec![4], &vec![6], &vec![9]].into());
+        assert_eq!(
+            k1.entry[&1][&vec![4]][&u].iter().collect::<Vec<_>>(),
+            [Sign::Zero]
+        );
+        assert_eq!(
+            k1.entry[&1][&vec![6]][&u].iter().collect::<Vec<_>>(),
+            [Sign::Pos]
+        );
+
+        let k2 = call_strings::run(&program, &ds, 2);
+        assert!(k2.entry[&1].keys().any(|context| context.len() == 2));
+        assert_ne!(k1.entry, k2.entry);
+    }
+
+    #[test]
+    fn call_strings_of_length_zero_are_context_insensitive() {
+        let program = parser::parse(FIB).unwrap();
+        let ds = DetectionOfSigns::new(&program);
+
+        let k0 = call_strings::run(&program, &ds, 0);
+        assert!(k0.entry.values().all(|contexts| contexts.len() <= 1));
+        assert_eq!(k0.merge(&ds), mfp::run(&program, &ds));
+    }
 }
@@ -1,95 +1,287 @@
-use std::{
-    collections::{HashMap, HashSet},
-    ops::Sub,
-};
+#![allow(dead_code)]
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
-    block::{AssignmentBlock, Block, TestBlock},
-    expression::{Label, Variable},
+    analysis::pt::PointsTo,
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock},
+    expression::{AExp, Label, Value, Variable},
     program::Program,
 };
 
-pub fn gen_lv(block: Block) -> HashSet<Variable> {
-    match block {
-        Block::Assignment(AssignmentBlock { expr, .. }) => expr.free_vars(),
-        Block::Test(TestBlock { expr, .. }) => expr.free_vars(),
-        Block::Skip(_) => [].into(),
+/// available copies
+pub mod ac;
+/// available expressions
+pub mod ae;
+/// use-definition and definition-use chains
+pub mod chains;
+/// constant propagation
+pub mod cp;
+/// detection of signs
+pub mod ds;
+/// interprocedural live variables
+pub mod ilv;
+/// intervals
+pub mod interval;
+/// live variables
+pub mod lv;
+/// points-to sets
+pub mod pt;
+/// reaching definitions
+pub mod rd;
+/// strongly live variables
+pub mod slv;
+/// very busy expressions
+pub mod vb;
+
+/// the direction in which information is propagated through a program
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    /// along `flow`, starting at the initial label
+    Forward,
+
+    /// along `flow_r`, starting at the final labels
+    Backward,
+}
+
+/// an instance of the monotone framework, i.e. everything a solver needs to know about an analysis
+pub trait MonotoneFramework {
+    /// the property space of the analysis
+    type Lattice: Clone + PartialEq;
+
+    fn direction(&self) -> Direction;
+
+    /// the least element of the lattice; for a must-analysis, whose join is intersection, this
+    /// is the universe of the property (e.g. the set of all expressions), not the empty set
+    fn bottom(&self) -> Self::Lattice;
+
+    /// the value holding at the extremal labels
+    fn extremal_value(&self) -> Self::Lattice;
+
+    /// the least upper bound of two lattice values
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice;
+
+    /// the partial order of the lattice; `a <= b` iff `a` joined with `b` is `b`
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        &self.join(a, b) == b
+    }
+
+    /// the widening operator, applied at loop heads so that solvers terminate on lattices of
+    /// infinite height; defaults to the join
+    fn widen(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.join(a, b)
+    }
+
+    /// the narrowing operator, applied at loop heads to regain precision lost by widening;
+    /// defaults to the newer value
+    fn narrow(&self, _a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        b.clone()
+    }
+
+    /// the transfer function f_l of the block with label l
+    fn transfer(&self, block: &Block, state: &Self::Lattice) -> Self::Lattice;
+
+    /// refines the value propagated along an edge (l, l') of the flow, e.g. based on the
+    /// outcome of the test at l; defaults to the identity
+    fn transfer_edge(&self, _edge: (Label, Label), state: &Self::Lattice) -> Self::Lattice {
+        state.clone()
+    }
+
+    /// the flow relation F of the analysis, based on its direction
+    fn flow(&self, program: &Program) -> BTreeSet<(Label, Label)> {
+        match self.direction() {
+            Direction::Forward => program.flow(),
+            Direction::Backward => program.flow_r(),
+        }
+    }
+
+    /// the extremal labels E of the analysis, based on its direction
+    fn extremal_labels(&self, program: &Program) -> BTreeSet<Label> {
+        match self.direction() {
+            Direction::Forward => [program.init_label()].into(),
+            Direction::Backward => program.final_labels(),
+        }
+    }
+
+    /// the labels that cannot be reached from the extremal labels along the flow, e.g. those of
+    /// procedures that are never called; no value ever flows to them, so they stay at the least
+    /// element, which for a must-analysis claims that everything holds there
+    fn unreachable_labels(&self, program: &Program) -> BTreeSet<Label> {
+        let flow = self.flow(program);
+
+        let mut reached = self.extremal_labels(program);
+        let mut work_list: Vec<Label> = reached.iter().cloned().collect();
+        while let Some(l) = work_list.pop() {
+            for (_, l_p) in flow.iter().filter(|(l_2, _)| *l_2 == l) {
+                if reached.insert(*l_p) {
+                    work_list.push(*l_p);
+                }
+            }
+        }
+
+        (1..=program.len).filter(|l| !reached.contains(l)).collect()
     }
 }
 
-pub fn kill_lv(block: Block) -> HashSet<Variable> {
+/// the abstract values of the analyses mapping every variable to a single one of them, i.e.
+/// constant propagation, intervals and detection of signs
+pub trait AbstractValue: Copy {
+    /// the value of a variable nothing is known about
+    fn top() -> Self;
+
+    /// the least upper bound of two values
+    fn join(self, other: Self) -> Self;
+
+    /// the value of a number
+    fn constant(val: Value) -> Self;
+
+    /// applies one of the operators `+ - * /`
+    fn apply(self, op: &str, other: Self) -> Self;
+}
+
+/// evaluates an arithmetic expression on abstract values; the contents of arrays and the heap
+/// are not tracked, and neither are addresses, so they may have any value
+pub fn eval_aexp<V: AbstractValue>(expr: &AExp, state: &BTreeMap<Variable, V>) -> V {
+    match expr {
+        AExp::Variable(var) => state.get(var).copied().unwrap_or(V::top()),
+        AExp::Number(val) => V::constant(*val),
+        AExp::ArithmeticOp(lhs, op, rhs) => eval_aexp(lhs, state).apply(op, eval_aexp(rhs, state)),
+        AExp::ArrayElement(_, _) | AExp::AddressOf(_) | AExp::Deref(_) | AExp::New => V::top(),
+    }
+}
+
+/// the abstract value of every variable after a block, given their values before it
+pub fn transfer_values<V: AbstractValue>(
+    block: &Block,
+    entry: &BTreeMap<Variable, V>,
+    pt: &PointsTo,
+) -> BTreeMap<Variable, V> {
+    let mut exit = entry.clone();
     match block {
-        Block::Assignment(AssignmentBlock { var, .. }) => [var].into(),
-        Block::Test(TestBlock { .. }) => [].into(),
-        Block::Skip(_) => [].into(),
+        Block::Assignment(AssignmentBlock { var, expr, .. }) => {
+            exit.insert(*var, eval_aexp(expr, entry));
+        }
+        // a weak update, as only one element is assigned and the others keep their values
+        Block::ArrayAssignment(ArrayAssignmentBlock { array, expr, .. }) => {
+            exit.insert(*array, entry[array].join(eval_aexp(expr, entry)));
+        }
+        // nothing is known about the input
+        Block::Read(ReadBlock { var, .. }) => {
+            exit.insert(*var, V::top());
+        }
+        // a weak update of every variable the pointer may point to
+        Block::Store(StoreBlock { ptr, expr, .. }) => {
+            let value = eval_aexp(expr, entry);
+            for var in pt.targets(*ptr) {
+                exit.insert(var, entry[&var].join(value));
+            }
+        }
+        Block::Test(_) | Block::Skip(_) | Block::Write(_) => {}
+        Block::Entry(_) | Block::Exit(_) => {}
+        // all parameters are evaluated before any of them is assigned
+        Block::Call(_) | Block::Return(_) => {
+            for (var, expr) in block.bindings() {
+                exit.insert(var, eval_aexp(&expr, entry));
+            }
+        }
     }
+    exit
 }
 
+/// the result of an analysis: the lattice values at the entry and exit of every label
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct LVAnalysis {
-    pub exit: LVExit,
-    pub entry: LVEntry,
+pub struct Analysis<L> {
+    pub entry: BTreeMap<Label, L>,
+    pub exit: BTreeMap<Label, L>,
+
+    /// the labels whose values are meaningless as they are never reached, see
+    /// [`MonotoneFramework::unreachable_labels`]
+    pub unreachable: BTreeSet<Label>,
 }
-impl LVAnalysis {
-    pub fn new(label_count: usize) -> Self {
-        Self {
-            exit: (1..=label_count)
-                .map(|label| (label, HashSet::new()))
-                .collect(),
-            entry: (1..=label_count)
-                .map(|label| (label, HashSet::new()))
-                .collect(),
+impl<L> Analysis<L> {
+    /// arranges the values before (context) and after (effect) the transfer functions of each
+    /// label into entry and exit values, depending on the direction of the analysis
+    pub fn new<F: MonotoneFramework<Lattice = L>>(
+        framework: &F,
+        program: &Program,
+        context: BTreeMap<Label, L>,
+        effect: BTreeMap<Label, L>,
+    ) -> Self {
+        let unreachable = framework.unreachable_labels(program);
+        match framework.direction() {
+            Direction::Forward => Self {
+                entry: context,
+                exit: effect,
+                unreachable,
+            },
+            Direction::Backward => Self {
+                entry: effect,
+                exit: context,
+                unreachable,
+            },
         }
     }
 }
 
-pub type LVExitAtLabel = HashSet<Variable>;
-pub type LVExit = HashMap<Label, HashSet<Variable>>;
-pub type LVEntryAtLabel = HashSet<Variable>;
-pub type LVEntry = HashMap<Label, HashSet<Variable>>;
+/// the most recent calls leading to a point of the program, identified by the labels of the
+/// calls and ordered from the most recent one
+pub type CallString = Vec<Label>;
 
-/// return the LVExit' mapping based on LVEntry
-pub fn lv_exit(program: &Program, lv_entry: &LVEntry) -> LVExit {
-    (1..=program.len)
-        .map(|label| (label, lv_exit_at(program, lv_entry, label)))
-        .collect()
+/// the result of a context-sensitive analysis: the lattice values at the entry and exit of
+/// every label, for each call string the label is reached with
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ContextAnalysis<L> {
+    /// the maximal length k of the call strings
+    pub depth: usize,
+    pub entry: BTreeMap<Label, BTreeMap<CallString, L>>,
+    pub exit: BTreeMap<Label, BTreeMap<CallString, L>>,
 }
+impl<L> ContextAnalysis<L> {
+    /// the context-insensitive view of the result, joining the values of all call strings
+    pub fn merge<F: MonotoneFramework<Lattice = L>>(&self, framework: &F) -> Analysis<L> {
+        let merge = |values: &BTreeMap<Label, BTreeMap<CallString, L>>| {
+            values
+                .iter()
+                .map(|(label, contexts)| {
+                    let value = contexts
+                        .values()
+                        .fold(framework.bottom(), |acc, value| framework.join(&acc, value));
+                    (*label, value)
+                })
+                .collect()
+        };
 
-/// return LVExit'(l) based on LVEntry
-pub fn lv_exit_at(program: &Program, lv_entry: &LVEntry, label: Label) -> LVExitAtLabel {
-    assert!(
-        program.at(label) != None,
-        "Label '{}' does not exist in program",
-        label
-    );
-
-    if program.final_labels().contains(&label) {
-        HashSet::new()
-    } else {
-        program
-            .flow_r()
-            .iter()
-            .filter(|(_, l)| l == &label)
-            .map(|(l_prime, _)| lv_entry[l_prime].clone())
-            .flatten()
-            .collect()
+        Analysis {
+            entry: merge(&self.entry),
+            exit: merge(&self.exit),
+            unreachable: self
+                .entry
+                .iter()
+                .filter(|(_, contexts)| contexts.is_empty())
+                .map(|(label, _)| *label)
+                .collect(),
+        }
     }
 }
 
-/// return the LVEntry' mapping based on LVExit
-pub fn lv_entry(program: &Program, lv_exit: &LVExit) -> LVExit {
-    (1..=program.len)
-        .map(|label| (label, lv_entry_at(program, lv_exit, label)))
-        .collect()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::cp::ConstValue, parser};
+
+    #[test]
+    fn array_assignments_are_weak_updates() {
+        let program = parser::parse("a[j] := 2; a[i] := 1; a[i] := 2 ").unwrap();
+        let pt = PointsTo::new(&program);
+        let a = Variable::new("a");
 
-/// return LVEntry'(l) based on LVExit
-pub fn lv_entry_at(program: &Program, lv_exit: &LVExit, label: Label) -> LVEntryAtLabel {
-    let block = program.at(label).unwrap();
+        let after = |label: Label, value: ConstValue| {
+            let entry = [(a, value)].into();
+            transfer_values(&program.at(label).unwrap(), &entry, &pt)[&a]
+        };
 
-    lv_exit[&label]
-        .sub(&kill_lv(block.clone()))
-        .union(&gen_lv(block))
-        .cloned()
-        .collect()
+        // the value assigned to one element is joined with those of the others
+        assert_eq!(after(1, ConstValue::Bottom), ConstValue::Const(2));
+        assert_eq!(after(2, ConstValue::Const(2)), ConstValue::Top);
+        assert_eq!(after(3, ConstValue::Const(2)), ConstValue::Const(2));
+    }
 }
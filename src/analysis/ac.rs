@@ -0,0 +1,130 @@
+#![allow(dead_code)]
+use std::{collections::BTreeSet, ops::Sub};
+
+use crate::{
+    analysis::{pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock},
+    expression::{AExp, Variable},
+    program::Program,
+};
+
+/// a copy assignment x := y, represented as (x, y)
+pub type CopyAssignment = (Variable, Variable);
+
+pub fn gen_ac(block: Block) -> BTreeSet<CopyAssignment> {
+    match block {
+        Block::Assignment(AssignmentBlock {
+            var,
+            expr: AExp::Variable(source),
+            ..
+        }) if var != source => [(var, source)].into(),
+        Block::Assignment(_) | Block::ArrayAssignment(_) | Block::Store(_) => [].into(),
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Read(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        // the parameters are passed simultaneously, so a copy from a variable that is assigned
+        // at the same time does not hold afterwards
+        Block::Call(_) | Block::Return(_) => {
+            let bindings = block.bindings();
+            bindings
+                .iter()
+                .filter_map(|(var, expr)| match expr {
+                    AExp::Variable(source)
+                        if var != source && bindings.iter().all(|(x, _)| x != source) =>
+                    {
+                        Some((*var, *source))
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+}
+
+/// kill_AC(B^l) based on the set of all copy assignments in the program
+pub fn kill_ac(
+    block: Block,
+    copies: &BTreeSet<CopyAssignment>,
+    pt: &PointsTo,
+) -> BTreeSet<CopyAssignment> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, .. })
+        | Block::ArrayAssignment(ArrayAssignmentBlock { array: var, .. })
+        | Block::Read(ReadBlock { var, .. }) => copies
+            .iter()
+            .filter(|(x, y)| x == &var || y == &var)
+            .cloned()
+            .collect(),
+        Block::Store(StoreBlock { ptr, .. }) => {
+            let targets = pt.targets(ptr);
+            copies
+                .iter()
+                .filter(|(x, y)| targets.contains(x) || targets.contains(y))
+                .cloned()
+                .collect()
+        }
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => {
+            let bindings = block.bindings();
+            copies
+                .iter()
+                .filter(|(x, y)| bindings.iter().any(|(var, _)| x == var || y == var))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+/// available-copies analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct AvailableCopies {
+    /// all copy assignments of the program
+    copies: BTreeSet<CopyAssignment>,
+
+    points_to: PointsTo,
+}
+impl AvailableCopies {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            copies: program.blocks().into_iter().flat_map(gen_ac).collect(),
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for AvailableCopies {
+    type Lattice = BTreeSet<CopyAssignment>;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.copies.clone()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.intersection(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_superset(b)
+    }
+
+    /// ACExit(l) = (ACEntry(l) \ kill_AC(B^l)) U gen_AC(B^l)
+    fn transfer(&self, block: &Block, ac_entry: &Self::Lattice) -> Self::Lattice {
+        ac_entry
+            .sub(&kill_ac(block.clone(), &self.copies, &self.points_to))
+            .union(&gen_ac(block.clone()))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type ACAnalysis = Analysis<BTreeSet<CopyAssignment>>;
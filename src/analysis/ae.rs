@@ -0,0 +1,182 @@
+#![allow(dead_code)]
+use std::{collections::BTreeSet, ops::Sub};
+
+use crate::{
+    analysis::{pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{
+        ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock, TestBlock, WriteBlock,
+    },
+    expression::AExp,
+    program::Program,
+};
+
+pub fn gen_ae(block: Block, pt: &PointsTo) -> BTreeSet<AExp> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, expr, .. }) => expr
+            .subexpressions()
+            .into_iter()
+            .filter(|a| !a.contains_var(&var))
+            .collect(),
+        Block::ArrayAssignment(ArrayAssignmentBlock {
+            array, index, expr, ..
+        }) => index
+            .subexpressions()
+            .into_iter()
+            .chain(expr.subexpressions())
+            .filter(|a| !a.contains_var(&array))
+            .collect(),
+        Block::Store(StoreBlock { ptr, expr, .. }) => {
+            let targets = pt.targets(ptr);
+            expr.subexpressions()
+                .into_iter()
+                .filter(|a| !targets.iter().any(|var| a.contains_var(var)))
+                .collect()
+        }
+        Block::Test(TestBlock { expr, .. }) => expr.subexpressions(),
+        Block::Write(WriteBlock { expr, .. }) => expr.subexpressions(),
+        Block::Skip(_) | Block::Read(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => {
+            let bindings = block.bindings();
+            bindings
+                .iter()
+                .flat_map(|(_, expr)| expr.subexpressions())
+                .filter(|a| !bindings.iter().any(|(var, _)| a.contains_var(var)))
+                .collect()
+        }
+    }
+}
+
+/// kill_AE(B^l) based on the set of all non-trivial arithmetic expressions AExp*
+pub fn kill_ae(block: Block, aexp_star: &BTreeSet<AExp>, pt: &PointsTo) -> BTreeSet<AExp> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, .. })
+        | Block::ArrayAssignment(ArrayAssignmentBlock { array: var, .. })
+        | Block::Read(ReadBlock { var, .. }) => aexp_star
+            .iter()
+            .filter(|a| a.contains_var(&var))
+            .cloned()
+            .collect(),
+        Block::Store(StoreBlock { ptr, .. }) => {
+            let targets = pt.targets(ptr);
+            aexp_star
+                .iter()
+                .filter(|a| targets.iter().any(|var| a.contains_var(var)))
+                .cloned()
+                .collect()
+        }
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => {
+            let bindings = block.bindings();
+            aexp_star
+                .iter()
+                .filter(|a| bindings.iter().any(|(var, _)| a.contains_var(var)))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+/// available-expressions analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct AvailableExpressions {
+    /// AExp*, the non-trivial arithmetic expressions of the program
+    aexp_star: BTreeSet<AExp>,
+
+    points_to: PointsTo,
+}
+impl AvailableExpressions {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            aexp_star: program.aexp_star(),
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for AvailableExpressions {
+    type Lattice = BTreeSet<AExp>;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.aexp_star.clone()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.intersection(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_superset(b)
+    }
+
+    /// AEExit(l) = (AEEntry(l) \ kill_AE(B^l)) U gen_AE(B^l)
+    fn transfer(&self, block: &Block, ae_entry: &Self::Lattice) -> Self::Lattice {
+        ae_entry
+            .sub(&kill_ae(block.clone(), &self.aexp_star, &self.points_to))
+            .union(&gen_ae(block.clone(), &self.points_to))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type AEAnalysis = Analysis<BTreeSet<AExp>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, expression::Label, output::text, parser};
+
+    fn exprs(program: &Program, labels: &[Label]) -> BTreeSet<AExp> {
+        labels
+            .iter()
+            .flat_map(|label| match program.at(*label) {
+                Some(Block::Assignment(AssignmentBlock { expr, .. })) => expr.subexpressions(),
+                _ => panic!("expected an assignment at label {label}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expressions_are_available_only_if_computed_on_all_paths() {
+        let program = parser::parse(
+            "read c; x := a + b; if c > 0 then y := a * b else a := 1 endif; z := a + b ",
+        )
+        .unwrap();
+        let ae = AvailableExpressions::new(&program);
+        let ana = algorithm::mfp::run(&program, &ae);
+
+        // a+b is killed on one branch, a*b only computed on the other
+        assert_eq!(ana.exit[&4], exprs(&program, &[2, 4]));
+        assert_eq!(ana.exit[&5], [].into());
+        assert_eq!(ana.entry[&6], [].into());
+        assert_eq!(ae.join(&ana.exit[&4], &ana.exit[&5]), [].into());
+        assert_eq!(ae.join(&ana.exit[&4], &ae.bottom()), ana.exit[&4]);
+    }
+
+    #[test]
+    fn unreachable_labels_are_marked() {
+        let program =
+            parser::parse("begin proc p(val a, res b) is b := a + 1 end; x := y * 2 end ").unwrap();
+        let ae = AvailableExpressions::new(&program);
+        let ana = algorithm::mfp::run(&program, &ae);
+
+        // the least element is the set of all expressions, which is all the procedure gets
+        assert_eq!(ana.entry[&2], ae.bottom());
+        assert_eq!(ana.unreachable, [1, 2, 3].into());
+        assert_eq!(ana.entry[&4], [].into());
+
+        let text = text::render(&program, &ana);
+        assert!(text.contains("2: unreachable\n"));
+        assert!(!text.contains("a+1"));
+    }
+}
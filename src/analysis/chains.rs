@@ -0,0 +1,79 @@
+#![allow(dead_code)]
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    analysis::{
+        lv::gen_lv,
+        pt::PointsTo,
+        rd::{gen_rd, Definition, RDAnalysis},
+    },
+    expression::{Label, Variable},
+    program::Program,
+};
+
+/// a use of a variable at a label
+pub type Use = (Variable, Label);
+
+/// ud(x, l): the definitions of x (or `?` if it may be uninitialized) reaching its use at l
+pub type UDChains = BTreeMap<Use, BTreeSet<Option<Label>>>;
+
+/// du(x, l): the labels at which the definition of x at l (or `?`) is used
+pub type DUChains = BTreeMap<Definition, BTreeSet<Label>>;
+
+/// computes ud(x, l) for every variable x used at a label l, based on RDEntry(l)
+pub fn ud_chains(program: &Program, rda: &RDAnalysis) -> UDChains {
+    let pt = PointsTo::new(program);
+    (1..=program.len)
+        .flat_map(|label| {
+            let used = gen_lv(program.at(label).unwrap(), &pt);
+
+            used.into_iter().map(move |var| {
+                let defs = rda.entry[&label]
+                    .iter()
+                    .filter(|(x, _)| x == &var)
+                    .map(|(_, def)| *def)
+                    .collect();
+
+                ((var, label), defs)
+            })
+        })
+        .collect()
+}
+
+/// the ud-chains ordered by the label of the use, then by variable
+pub fn ud_by_label(ud: &UDChains) -> Vec<(&Use, &BTreeSet<Option<Label>>)> {
+    let mut uses: Vec<_> = ud.iter().collect();
+    uses.sort_by_key(|((var, label), _)| (*label, *var));
+    uses
+}
+
+/// computes du(x, l) for every definition in the program by inverting the ud-chains
+pub fn du_chains(program: &Program, ud: &UDChains) -> DUChains {
+    let pt = PointsTo::new(program);
+    let definitions = program
+        .free_vars()
+        .into_iter()
+        .map(|var| (var, None))
+        .chain(
+            program
+                .blocks()
+                .into_iter()
+                .flat_map(|block| gen_rd(block, &pt)),
+        );
+    let mut du: DUChains = definitions.map(|def| (def, BTreeSet::new())).collect();
+
+    for ((var, label), defs) in ud {
+        for def in defs {
+            du.entry((*var, *def)).or_default().insert(*label);
+        }
+    }
+
+    du
+}
+
+/// the du-chains ordered by the label of the definition (`?` first), then by variable
+pub fn du_by_label(du: &DUChains) -> Vec<(&Definition, &BTreeSet<Label>)> {
+    let mut defs: Vec<_> = du.iter().collect();
+    defs.sort_by_key(|((var, label), _)| (*label, *var));
+    defs
+}
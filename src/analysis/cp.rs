@@ -0,0 +1,188 @@
+#![allow(dead_code)]
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    analysis::{
+        eval_aexp, pt::PointsTo, transfer_values, AbstractValue, Analysis, Direction,
+        MonotoneFramework,
+    },
+    block::{Block, TestBlock},
+    expression::{eval_arithmetic_op, eval_boolean_op, eval_relational_op, BExp, Value, Variable},
+    program::Program,
+};
+
+/// the flat lattice of a single variable: no value yet, exactly one value, or any value
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum ConstValue {
+    Bottom,
+    Const(Value),
+    Top,
+}
+impl AbstractValue for ConstValue {
+    fn top() -> Self {
+        Self::Top
+    }
+
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Bottom, x) | (x, Self::Bottom) => x,
+            (Self::Const(a), Self::Const(b)) if a == b => Self::Const(a),
+            _ => Self::Top,
+        }
+    }
+
+    fn constant(val: Value) -> Self {
+        Self::Const(val)
+    }
+
+    fn apply(self, op: &str, other: Self) -> Self {
+        match (self, other) {
+            (Self::Bottom, _) | (_, Self::Bottom) => Self::Bottom,
+            (Self::Const(a), Self::Const(b)) => match eval_arithmetic_op(a, op, b) {
+                Some(val) => Self::Const(val),
+                None => Self::Top,
+            },
+            _ => Self::Top,
+        }
+    }
+}
+
+/// maps every variable of the program to its abstract value
+pub type CPState = BTreeMap<Variable, ConstValue>;
+
+/// evaluates a boolean expression on abstract values; `None` if its value is not constant
+pub fn eval_bexp(expr: &BExp, state: &CPState) -> Option<bool> {
+    match expr {
+        BExp::True => Some(true),
+        BExp::False => Some(false),
+        BExp::Not(inner) => eval_bexp(inner, state).map(|b| !b),
+        BExp::BooleanOp(lhs, op, rhs) => {
+            let (lhs, rhs) = (eval_bexp(lhs, state), eval_bexp(rhs, state));
+            match (lhs, op.as_str(), rhs) {
+                (Some(false), "&&", _) | (_, "&&", Some(false)) => Some(false),
+                (Some(true), "||", _) | (_, "||", Some(true)) => Some(true),
+                (Some(a), _, Some(b)) => Some(eval_boolean_op(a, op, b)),
+                _ => None,
+            }
+        }
+        BExp::RelationalOp(lhs, op, rhs) => match (eval_aexp(lhs, state), eval_aexp(rhs, state)) {
+            (ConstValue::Const(a), ConstValue::Const(b)) => Some(eval_relational_op(a, op, b)),
+            _ => None,
+        },
+    }
+}
+
+/// constant-propagation analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct ConstantPropagation {
+    /// the variables of the program
+    vars: Vec<Variable>,
+
+    points_to: PointsTo,
+}
+impl ConstantPropagation {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            vars: program.free_vars().into_iter().collect(),
+            points_to: PointsTo::new(program),
+        }
+    }
+
+    fn state(&self, value: ConstValue) -> CPState {
+        self.vars.iter().map(|var| (*var, value)).collect()
+    }
+}
+
+impl MonotoneFramework for ConstantPropagation {
+    type Lattice = CPState;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.state(ConstValue::Bottom)
+    }
+
+    /// nothing is known about the variables at the start of the program
+    fn extremal_value(&self) -> Self::Lattice {
+        self.state(ConstValue::Top)
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.vars
+            .iter()
+            .map(|var| (*var, a[var].join(b[var])))
+            .collect()
+    }
+
+    fn transfer(&self, block: &Block, cp_entry: &Self::Lattice) -> Self::Lattice {
+        transfer_values(block, cp_entry, &self.points_to)
+    }
+}
+
+pub type CPAnalysis = Analysis<CPState>;
+
+/// the tests whose outcome is already known whenever they are reached, with that outcome
+pub fn constant_tests(program: &Program, cpa: &CPAnalysis) -> Vec<(TestBlock, bool)> {
+    (1..=program.len)
+        .filter_map(|label| match program.at(label) {
+            Some(Block::Test(test)) => {
+                eval_bexp(&test.expr, &cpa.entry[&label]).map(|value| (test, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstValue::Bottom => write!(f, "⊥"),
+            ConstValue::Const(val) => write!(f, "{}", val),
+            ConstValue::Top => write!(f, "⊤"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, expression::Label, parser};
+
+    #[test]
+    fn constants_are_propagated_until_they_differ() {
+        let program = parser::parse(
+            "x := 2; y := x * 3; read c; if c > 0 then z := 1 else z := 2 endif; w := z ",
+        )
+        .unwrap();
+        let ana = algorithm::mfp::run(&program, &ConstantPropagation::new(&program));
+        let var = Variable::new;
+
+        assert_eq!(ana.exit[&2][&var("y")], ConstValue::Const(6));
+        assert_eq!(ana.exit[&3][&var("c")], ConstValue::Top);
+        assert_eq!(ana.exit[&5][&var("z")], ConstValue::Const(1));
+        // z is 1 on one branch and 2 on the other
+        assert_eq!(ana.entry[&7][&var("z")], ConstValue::Top);
+        assert_eq!(ana.exit[&7][&var("w")], ConstValue::Top);
+        assert_eq!(ana.exit[&7][&var("y")], ConstValue::Const(6));
+    }
+
+    #[test]
+    fn tests_with_a_known_outcome_are_found() {
+        let program = parser::parse(
+            "x := 2; if x > 1 then y := 1 else y := 2 endif; \
+             while x == 5 do y := y + 1 enddo; read x; if x > 1 then skip else skip endif ",
+        )
+        .unwrap();
+        let cpa = algorithm::mfp::run(&program, &ConstantPropagation::new(&program));
+        let tests: Vec<(Label, bool)> = constant_tests(&program, &cpa)
+            .into_iter()
+            .map(|(test, value)| (test.label, value))
+            .collect();
+
+        assert_eq!(tests, [(2, true), (5, false)]);
+        // the branch not taken is not pruned, so y is not constant after the first test
+        assert_eq!(cpa.entry[&5][&Variable::new("y")], ConstValue::Top);
+    }
+}
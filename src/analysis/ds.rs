@@ -0,0 +1,205 @@
+#![allow(dead_code)]
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    analysis::{
+        pt::PointsTo, transfer_values, AbstractValue, Analysis, Direction, MonotoneFramework,
+    },
+    block::Block,
+    expression::{Value, Variable},
+    program::Program,
+};
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Sign {
+    Neg,
+    Zero,
+    Pos,
+}
+impl Sign {
+    pub fn of(val: Value) -> Self {
+        match val.signum() {
+            -1 => Self::Neg,
+            0 => Self::Zero,
+            _ => Self::Pos,
+        }
+    }
+}
+
+/// a subset of {-, 0, +}
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Default, Debug)]
+pub struct Signs {
+    pub neg: bool,
+    pub zero: bool,
+    pub pos: bool,
+}
+impl Signs {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn all() -> Self {
+        Self {
+            neg: true,
+            zero: true,
+            pos: true,
+        }
+    }
+
+    pub fn contains(self, sign: Sign) -> bool {
+        match sign {
+            Sign::Neg => self.neg,
+            Sign::Zero => self.zero,
+            Sign::Pos => self.pos,
+        }
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Sign> {
+        [Sign::Neg, Sign::Zero, Sign::Pos]
+            .into_iter()
+            .filter(move |sign| self.contains(*sign))
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            neg: self.neg || other.neg,
+            zero: self.zero || other.zero,
+            pos: self.pos || other.pos,
+        }
+    }
+}
+impl AbstractValue for Signs {
+    fn top() -> Self {
+        Self::all()
+    }
+
+    fn join(self, other: Self) -> Self {
+        self.union(other)
+    }
+
+    fn constant(val: Value) -> Self {
+        [Sign::of(val)].into_iter().collect()
+    }
+
+    /// applies an arithmetic operator to every combination of signs of its operands
+    fn apply(self, op: &str, other: Self) -> Self {
+        self.iter()
+            .flat_map(|a| other.iter().map(move |b| apply_sign_op(a, op, b)))
+            .fold(Self::empty(), Self::union)
+    }
+}
+impl FromIterator<Sign> for Signs {
+    fn from_iter<T: IntoIterator<Item = Sign>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::empty(), |signs, sign| match sign {
+                Sign::Neg => Signs { neg: true, ..signs },
+                Sign::Zero => Signs {
+                    zero: true,
+                    ..signs
+                },
+                Sign::Pos => Signs { pos: true, ..signs },
+            })
+    }
+}
+
+/// the possible signs of `a op b` for operands with signs `a` and `b`
+fn apply_sign_op(a: Sign, op: &str, b: Sign) -> Signs {
+    use Sign::*;
+    match (op, a, b) {
+        ("+", Zero, x) | ("+", x, Zero) => [x].into_iter().collect(),
+        ("+", Neg, Neg) => [Neg].into_iter().collect(),
+        ("+", Pos, Pos) => [Pos].into_iter().collect(),
+        ("+", _, _) => Signs::all(),
+
+        ("-", x, Zero) => [x].into_iter().collect(),
+        ("-", Zero, Neg) => [Pos].into_iter().collect(),
+        ("-", Zero, Pos) => [Neg].into_iter().collect(),
+        ("-", Neg, Pos) => [Neg].into_iter().collect(),
+        ("-", Pos, Neg) => [Pos].into_iter().collect(),
+        ("-", _, _) => Signs::all(),
+
+        ("*", Zero, _) | ("*", _, Zero) => [Zero].into_iter().collect(),
+        ("*", x, y) if x == y => [Pos].into_iter().collect(),
+        ("*", _, _) => [Neg].into_iter().collect(),
+
+        // division by zero has no result
+        ("/", _, Zero) => Signs::empty(),
+        ("/", Zero, _) => [Zero].into_iter().collect(),
+        // integer division truncates towards zero
+        ("/", x, y) if x == y => [Zero, Pos].into_iter().collect(),
+        ("/", _, _) => [Neg, Zero].into_iter().collect(),
+
+        _ => unreachable!("unknown arithmetic operator '{op}'"),
+    }
+}
+
+/// maps every variable of the program to the signs it may have
+pub type DSState = BTreeMap<Variable, Signs>;
+
+/// detection-of-signs analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct DetectionOfSigns {
+    /// the variables of the program
+    vars: Vec<Variable>,
+
+    points_to: PointsTo,
+}
+impl DetectionOfSigns {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            vars: program.free_vars().into_iter().collect(),
+            points_to: PointsTo::new(program),
+        }
+    }
+
+    fn state(&self, value: Signs) -> DSState {
+        self.vars.iter().map(|var| (*var, value)).collect()
+    }
+}
+
+impl MonotoneFramework for DetectionOfSigns {
+    type Lattice = DSState;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.state(Signs::empty())
+    }
+
+    /// nothing is known about the variables at the start of the program
+    fn extremal_value(&self) -> Self::Lattice {
+        self.state(Signs::all())
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.vars
+            .iter()
+            .map(|var| (*var, a[var].union(b[var])))
+            .collect()
+    }
+
+    fn transfer(&self, block: &Block, ds_entry: &Self::Lattice) -> Self::Lattice {
+        transfer_values(block, ds_entry, &self.points_to)
+    }
+}
+
+pub type DSAnalysis = Analysis<DSState>;
+
+impl Display for Sign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sign::Neg => write!(f, "-"),
+            Sign::Zero => write!(f, "0"),
+            Sign::Pos => write!(f, "+"),
+        }
+    }
+}
+
+impl Display for Signs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signs: Vec<String> = self.iter().map(|sign| sign.to_string()).collect();
+        write!(f, "{{{}}}", signs.join(", "))
+    }
+}
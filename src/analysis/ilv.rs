@@ -0,0 +1,221 @@
+#![allow(dead_code)]
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    analysis::{lv::LiveVariables, pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{Block, CallBlock},
+    expression::{Label, Variable},
+    program::Program,
+};
+
+/// a call together with the locals of the procedure it occurs in
+#[derive(Clone, Debug)]
+struct CallSite {
+    call: CallBlock,
+    caller_locals: BTreeSet<Variable>,
+}
+
+/// interprocedural live-variable analysis as an instance of the monotone framework
+///
+/// The formal parameters of a procedure are local to its body, all other variables are
+/// global. Going backwards, the globals and result parameters live after a call flow from its
+/// return label through the procedure to its call label, where the value parameters live at
+/// the entry of the procedure make the variables of their arguments live. The locals of the
+/// caller are saved across the call, so they bypass the procedure along an additional edge
+/// from the return label to the call label.
+#[derive(Clone, Debug)]
+pub struct InterproceduralLiveVariables {
+    /// the calls of the program by their call labels
+    calls: BTreeMap<Label, CallSite>,
+
+    /// live variables within procedures and the main program
+    lv: LiveVariables,
+
+    points_to: PointsTo,
+}
+impl InterproceduralLiveVariables {
+    pub fn new(program: &Program) -> Self {
+        let calls = program
+            .blocks()
+            .into_iter()
+            .filter_map(|block| match block {
+                Block::Call(call) => Some(call),
+                _ => None,
+            })
+            .map(|call| {
+                let caller_locals = program
+                    .procedure_at(call.label)
+                    .map(|proc| proc.locals())
+                    .unwrap_or_default();
+                (
+                    call.label,
+                    CallSite {
+                        call,
+                        caller_locals,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            calls,
+            lv: LiveVariables::new(program),
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for InterproceduralLiveVariables {
+    type Lattice = BTreeSet<Variable>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.union(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_subset(b)
+    }
+
+    /// as for live variables, except that parameters are passed along the edges into and out
+    /// of procedures rather than at the call and return labels
+    fn transfer(&self, block: &Block, lv_exit: &Self::Lattice) -> Self::Lattice {
+        match block {
+            Block::Call(_) | Block::Return(_) => lv_exit.clone(),
+            _ => self.lv.transfer(block, lv_exit),
+        }
+    }
+
+    fn transfer_edge(&self, (l, l_p): (Label, Label), state: &Self::Lattice) -> Self::Lattice {
+        // from the return label into the exit of the procedure, or around it
+        if let Some(CallSite {
+            call,
+            caller_locals,
+        }) = self.calls.values().find(|site| site.call.ret == l)
+        {
+            let locals: BTreeSet<Variable> = caller_locals.union(&call.locals()).cloned().collect();
+            let live = state.iter().filter(|var| !call.results.contains(var));
+
+            return if l_p == call.exit {
+                live.filter(|var| !locals.contains(var))
+                    .chain(&call.res)
+                    .cloned()
+                    .collect()
+            } else {
+                live.filter(|var| locals.contains(var)).cloned().collect()
+            };
+        }
+
+        // from the entry of the procedure to the call label
+        match self.calls.get(&l_p) {
+            Some(CallSite { call, .. }) if l == call.entry => {
+                let locals = call.locals();
+                let args = call
+                    .val
+                    .iter()
+                    .zip(&call.args)
+                    .filter(|(x, _)| state.contains(x))
+                    .flat_map(|(_, arg)| self.points_to.reads(arg));
+
+                state
+                    .iter()
+                    .filter(|var| !locals.contains(var))
+                    .cloned()
+                    .chain(args)
+                    .collect()
+            }
+
+            _ => state.clone(),
+        }
+    }
+
+    /// flowᴿ together with an edge from the return label to the call label of every call
+    fn flow(&self, program: &Program) -> BTreeSet<(Label, Label)> {
+        program
+            .flow_r()
+            .into_iter()
+            .chain(
+                self.calls
+                    .values()
+                    .map(|site| (site.call.ret, site.call.label)),
+            )
+            .collect()
+    }
+}
+
+pub type ILVAnalysis = Analysis<BTreeSet<Variable>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, parser};
+
+    const FIB: &str = "begin
+        proc fib(val z, u, res v) is
+            if z < 3 then v := u + 1
+            else call fib(z - 1, u, v); call fib(z - 2, v, v) endif
+        end;
+        call fib(x, 0, y)
+    end ";
+
+    fn vars(names: &[&str]) -> BTreeSet<Variable> {
+        names.iter().map(|name| Variable::new(name)).collect()
+    }
+
+    #[test]
+    fn live_variables_of_fib() {
+        let program = parser::parse(FIB).unwrap();
+        let ana = algorithm::mfp::run(&program, &InterproceduralLiveVariables::new(&program));
+
+        let expected = [
+            (1, &["u", "z"][..]),
+            (2, &["u", "z"]),
+            (3, &["u"]),
+            (4, &["u", "z"]),
+            (5, &["v", "z"]),
+            (6, &["v", "z"]),
+            (7, &["v"]),
+            (8, &["v"]),
+            (9, &["x"]),
+            (10, &[]),
+        ];
+        for (label, live) in expected {
+            assert_eq!(ana.entry[&label], vars(live), "entry of {label}");
+        }
+        assert_eq!(ana.exit[&3], vars(&["v"]));
+    }
+
+    #[test]
+    fn parameters_are_passed_along_the_edges() {
+        let program = parser::parse(FIB).unwrap();
+        let ilv = InterproceduralLiveVariables::new(&program);
+
+        // into the procedure at its exit: the variable receiving the result is replaced by
+        // the result parameter
+        assert_eq!(
+            ilv.transfer_edge((10, 8), &vars(&["x", "y"])),
+            vars(&["x", "v"])
+        );
+        // around the procedure: only the locals of the caller, of which main has none
+        assert_eq!(ilv.transfer_edge((10, 9), &vars(&["x", "y"])), vars(&[]));
+        // out of the procedure at its entry: live value parameters make their arguments live
+        assert_eq!(ilv.transfer_edge((1, 9), &vars(&["z", "u"])), vars(&["x"]));
+        // between recursive calls, the locals of the caller are kept around the call
+        assert_eq!(ilv.transfer_edge((5, 4), &vars(&["v", "z"])), vars(&["z"]));
+        assert_eq!(
+            ilv.transfer_edge((1, 4), &vars(&["z", "u"])),
+            vars(&["u", "z"])
+        );
+    }
+}
@@ -0,0 +1,506 @@
+#![allow(dead_code)]
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use crate::{
+    analysis::{
+        eval_aexp, pt::PointsTo, transfer_values, AbstractValue, Analysis, Direction,
+        MonotoneFramework,
+    },
+    block::Block,
+    expression::{AExp, BExp, Label, Value, Variable},
+    program::Program,
+};
+
+/// a lower or upper bound of an interval
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Bound {
+    NegInf,
+    Finite(Value),
+    PosInf,
+}
+impl Bound {
+    fn signum(self) -> i32 {
+        match self {
+            Self::NegInf => -1,
+            Self::Finite(val) => val.signum(),
+            Self::PosInf => 1,
+        }
+    }
+
+    /// the infinite bound (or zero) with the given sign
+    fn with_signum(signum: i32) -> Self {
+        match signum {
+            0 => Self::Finite(0),
+            s if s > 0 => Self::PosInf,
+            _ => Self::NegInf,
+        }
+    }
+
+    // the operations below return `None` if the result cannot be represented
+
+    fn neg(self) -> Option<Self> {
+        match self {
+            Self::NegInf => Some(Self::PosInf),
+            Self::Finite(val) => val.checked_neg().map(Self::Finite),
+            Self::PosInf => Some(Self::NegInf),
+        }
+    }
+
+    fn add(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Finite(a), Self::Finite(b)) => a.checked_add(b).map(Self::Finite),
+            (Self::NegInf, Self::PosInf) | (Self::PosInf, Self::NegInf) => None,
+            (Self::NegInf, _) | (_, Self::NegInf) => Some(Self::NegInf),
+            _ => Some(Self::PosInf),
+        }
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Finite(a), Self::Finite(b)) => a.checked_mul(b).map(Self::Finite),
+            _ => Some(Self::with_signum(self.signum() * other.signum())),
+        }
+    }
+
+    /// division by a bound other than zero
+    fn div(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Finite(a), Self::Finite(b)) => a.checked_div(b).map(Self::Finite),
+            (Self::Finite(_), _) => Some(Self::Finite(0)),
+            (_, Self::Finite(_)) => Some(Self::with_signum(self.signum() * other.signum())),
+            // the other corners of the interval already cover the result
+            _ => Some(Self::Finite(0)),
+        }
+    }
+}
+
+/// a set of consecutive integers, possibly unbounded on either side
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum Interval {
+    Empty,
+
+    /// lower and upper bound (inclusive)
+    Range(Bound, Bound),
+}
+impl Interval {
+    pub fn new(lo: Bound, hi: Bound) -> Self {
+        if lo <= hi && lo != Bound::PosInf && hi != Bound::NegInf {
+            Self::Range(lo, hi)
+        } else {
+            Self::Empty
+        }
+    }
+
+    /// the smallest interval containing all given bounds, or ⊤ if one of them is not representable
+    fn hull(bounds: impl IntoIterator<Item = Option<Bound>>) -> Self {
+        let bounds: Option<Vec<Bound>> = bounds.into_iter().collect();
+        match bounds {
+            Some(bounds) => Self::new(*bounds.iter().min().unwrap(), *bounds.iter().max().unwrap()),
+            None => Self::top(),
+        }
+    }
+
+    pub fn meet(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::new(a.max(c), b.min(d)),
+        }
+    }
+
+    /// [a, b] ∇ [c, d]: bounds that are still moving are pushed to infinity
+    pub fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, x) | (x, Self::Empty) => x,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::Range(
+                if c < a { Bound::NegInf } else { a },
+                if d > b { Bound::PosInf } else { b },
+            ),
+        }
+    }
+
+    /// [a, b] Δ [c, d]: infinite bounds are replaced by the newer ones
+    pub fn narrow(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::Range(
+                if a == Bound::NegInf { c } else { a },
+                if b == Bound::PosInf { d } else { b },
+            ),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::hull([a.add(c), b.add(d)]),
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::hull([
+                d.neg().and_then(|d| a.add(d)),
+                c.neg().and_then(|c| b.add(c)),
+            ]),
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => {
+                Self::hull([a.mul(c), a.mul(d), b.mul(c), b.mul(d)])
+            }
+        }
+    }
+
+    fn div(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, _) | (_, Self::Empty) => Self::Empty,
+            (Self::Range(a, b), Self::Range(c, d)) => {
+                // divide by the negative and positive parts of the divisor separately
+                let negative = Self::new(c, d.min(Bound::Finite(-1)));
+                let positive = Self::new(c.max(Bound::Finite(1)), d);
+
+                [negative, positive]
+                    .into_iter()
+                    .map(|divisor| match divisor {
+                        Self::Empty => Self::Empty,
+                        Self::Range(c, d) => Self::hull([a.div(c), a.div(d), b.div(c), b.div(d)]),
+                    })
+                    .fold(Self::Empty, Self::join)
+            }
+        }
+    }
+
+    /// the values of the interval for which `x op other` may hold
+    fn restrict(self, op: &str, other: Self) -> Self {
+        let (lo, hi) = match other {
+            Self::Empty => return Self::Empty,
+            Self::Range(lo, hi) => (lo, hi),
+        };
+
+        let bounded = |lo: Option<Bound>, hi: Option<Bound>| match (lo, hi) {
+            (Some(lo), Some(hi)) => self.meet(Self::new(lo, hi)),
+            _ => self,
+        };
+
+        match op {
+            "<" => bounded(Some(Bound::NegInf), hi.add(Bound::Finite(-1))),
+            "<=" => bounded(Some(Bound::NegInf), Some(hi)),
+            ">" => bounded(lo.add(Bound::Finite(1)), Some(Bound::PosInf)),
+            ">=" => bounded(Some(lo), Some(Bound::PosInf)),
+            "==" => self.meet(other),
+            "!=" => match (self, lo == hi) {
+                (Self::Range(a, b), true) if a == lo => {
+                    Self::new(a.add(Bound::Finite(1)).unwrap_or(a), b)
+                }
+                (Self::Range(a, b), true) if b == lo => {
+                    Self::new(a, b.add(Bound::Finite(-1)).unwrap_or(b))
+                }
+                _ => self,
+            },
+            _ => unreachable!("unknown relational operator '{op}'"),
+        }
+    }
+}
+
+/// the relational operator that holds iff `op` does not
+fn negate(op: &str) -> &str {
+    match op {
+        "<" => ">=",
+        "<=" => ">",
+        "==" => "!=",
+        "!=" => "==",
+        ">=" => "<",
+        ">" => "<=",
+        _ => unreachable!("unknown relational operator '{op}'"),
+    }
+}
+
+/// the relational operator that holds for swapped operands iff `op` does
+fn mirror(op: &str) -> &str {
+    match op {
+        "<" => ">",
+        "<=" => ">=",
+        ">=" => "<=",
+        ">" => "<",
+        _ => op,
+    }
+}
+
+/// maps every variable of the program to the interval of values it may hold
+pub type IntervalState = BTreeMap<Variable, Interval>;
+
+/// interval analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct Intervals {
+    /// the variables of the program
+    vars: Vec<Variable>,
+
+    /// the condition of every if/while test, together with the label it continues at if true
+    branches: BTreeMap<Label, (BExp, Label)>,
+
+    points_to: PointsTo,
+}
+impl Intervals {
+    pub fn new(program: &Program) -> Self {
+        let true_flow: BTreeSet<(Label, Label)> = program.true_flow();
+
+        Self {
+            vars: program.free_vars().into_iter().collect(),
+            branches: true_flow
+                .into_iter()
+                .filter_map(|(l, l_true)| match program.at(l) {
+                    Some(Block::Test(test)) => Some((l, (test.expr, l_true))),
+                    _ => None,
+                })
+                .collect(),
+            points_to: PointsTo::new(program),
+        }
+    }
+
+    fn state(&self, value: Interval) -> IntervalState {
+        self.vars.iter().map(|var| (*var, value)).collect()
+    }
+
+    /// a state in which one variable has no possible value cannot be reached at all
+    fn normalize(&self, state: IntervalState) -> IntervalState {
+        if state.values().any(|val| val == &Interval::Empty) {
+            self.bottom()
+        } else {
+            state
+        }
+    }
+
+    /// restricts a state to those values for which `expr` may evaluate to `outcome`
+    pub fn refine(&self, expr: &BExp, outcome: bool, state: &IntervalState) -> IntervalState {
+        match expr {
+            BExp::True | BExp::False => {
+                if (expr == &BExp::True) == outcome {
+                    state.clone()
+                } else {
+                    self.bottom()
+                }
+            }
+
+            BExp::Not(inner) => self.refine(inner, !outcome, state),
+
+            BExp::BooleanOp(lhs, op, rhs) => {
+                // the outcome is decided by both operands if it is true for && or false for ||
+                if (op == "&&") == outcome {
+                    self.refine(rhs, outcome, &self.refine(lhs, outcome, state))
+                } else {
+                    self.join(
+                        &self.refine(lhs, outcome, state),
+                        &self.refine(rhs, outcome, state),
+                    )
+                }
+            }
+
+            BExp::RelationalOp(lhs, op, rhs) => {
+                let op = if outcome { op.as_str() } else { negate(op) };
+                let (lhs_val, rhs_val) = (eval_aexp(lhs, state), eval_aexp(rhs, state));
+
+                let mut refined = state.clone();
+                if let AExp::Variable(x) = lhs {
+                    refined.insert(*x, refined[x].restrict(op, rhs_val));
+                }
+                if let AExp::Variable(y) = rhs {
+                    refined.insert(*y, refined[y].restrict(mirror(op), lhs_val));
+                }
+
+                self.normalize(refined)
+            }
+        }
+    }
+}
+
+impl MonotoneFramework for Intervals {
+    type Lattice = IntervalState;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.state(Interval::Empty)
+    }
+
+    /// nothing is known about the variables at the start of the program
+    fn extremal_value(&self) -> Self::Lattice {
+        self.state(Interval::top())
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.vars
+            .iter()
+            .map(|var| (*var, a[var].join(b[var])))
+            .collect()
+    }
+
+    fn widen(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.vars
+            .iter()
+            .map(|var| (*var, a[var].widen(b[var])))
+            .collect()
+    }
+
+    fn narrow(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        self.normalize(
+            self.vars
+                .iter()
+                .map(|var| (*var, a[var].narrow(b[var])))
+                .collect(),
+        )
+    }
+
+    /// a variable assigned the empty interval makes the state after the block unreachable
+    fn transfer(&self, block: &Block, entry: &Self::Lattice) -> Self::Lattice {
+        self.normalize(transfer_values(block, entry, &self.points_to))
+    }
+
+    /// restricts the state leaving an if/while test to the values for which the branch is taken
+    fn transfer_edge(&self, (l, l_p): (Label, Label), state: &Self::Lattice) -> Self::Lattice {
+        match self.branches.get(&l) {
+            Some((expr, l_true)) => self.refine(expr, l_p == *l_true, state),
+            None => state.clone(),
+        }
+    }
+}
+
+pub type IntervalAnalysis = Analysis<IntervalState>;
+
+impl AbstractValue for Interval {
+    fn top() -> Self {
+        Self::Range(Bound::NegInf, Bound::PosInf)
+    }
+
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Empty, x) | (x, Self::Empty) => x,
+            (Self::Range(a, b), Self::Range(c, d)) => Self::Range(a.min(c), b.max(d)),
+        }
+    }
+
+    fn constant(val: Value) -> Self {
+        Self::Range(Bound::Finite(val), Bound::Finite(val))
+    }
+
+    fn apply(self, op: &str, other: Self) -> Self {
+        match op {
+            "+" => self.add(other),
+            "-" => self.sub(other),
+            "*" => self.mul(other),
+            "/" => self.div(other),
+            _ => unreachable!("unknown arithmetic operator '{op}'"),
+        }
+    }
+}
+
+impl Display for Bound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bound::NegInf => write!(f, "-∞"),
+            Bound::Finite(val) => write!(f, "{}", val),
+            Bound::PosInf => write!(f, "∞"),
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interval::Empty => write!(f, "⊥"),
+            Interval::Range(lo, hi) => write!(f, "[{}, {}]", lo, hi),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, parser};
+
+    fn range(lo: Value, hi: Value) -> Interval {
+        Interval::new(Bound::Finite(lo), Bound::Finite(hi))
+    }
+
+    #[test]
+    fn widen_pushes_growing_bounds_to_infinity() {
+        assert_eq!(
+            range(0, 1).widen(range(0, 2)),
+            Interval::new(Bound::Finite(0), Bound::PosInf)
+        );
+        assert_eq!(
+            range(0, 1).widen(range(-1, 1)),
+            Interval::new(Bound::NegInf, Bound::Finite(1))
+        );
+        assert_eq!(range(0, 5).widen(range(1, 2)), range(0, 5));
+        assert_eq!(Interval::Empty.widen(range(1, 2)), range(1, 2));
+    }
+
+    #[test]
+    fn narrow_replaces_only_infinite_bounds() {
+        let widened = Interval::new(Bound::Finite(0), Bound::PosInf);
+        assert_eq!(widened.narrow(range(0, 10)), range(0, 10));
+        assert_eq!(range(0, 5).narrow(range(1, 2)), range(0, 5));
+        assert_eq!(widened.narrow(Interval::Empty), Interval::Empty);
+    }
+
+    #[test]
+    fn overflow_goes_to_top() {
+        let max = range(Value::MAX, Value::MAX);
+        assert_eq!(max.add(range(1, 1)), Interval::top());
+        assert_eq!(max.mul(range(2, 2)), Interval::top());
+        assert_eq!(
+            range(Value::MIN, Value::MIN).sub(range(1, 1)),
+            Interval::top()
+        );
+    }
+
+    #[test]
+    fn division_by_zero_is_empty() {
+        assert_eq!(range(1, 5).div(range(0, 0)), Interval::Empty);
+        assert_eq!(range(1, 5).div(range(0, 1)), range(1, 5));
+        assert_eq!(range(-6, 6).div(range(-2, 2)), range(-6, 6));
+    }
+
+    #[test]
+    fn refine_restricts_variables_to_the_branch_taken() {
+        let program = parser::parse("while x < 10 do x := x + 1 enddo ").unwrap();
+        let intervals = Intervals::new(&program);
+        let Some(Block::Test(test)) = program.at(1) else {
+            panic!("expected a test at label 1")
+        };
+        let (x, expr) = (Variable::new("x"), test.expr);
+        let state: IntervalState = [(x, range(0, 20))].into();
+
+        assert_eq!(intervals.refine(&expr, true, &state)[&x], range(0, 9));
+        assert_eq!(intervals.refine(&expr, false, &state)[&x], range(10, 20));
+
+        let state: IntervalState = [(x, range(10, 20))].into();
+        assert_eq!(intervals.refine(&expr, true, &state), intervals.bottom());
+    }
+
+    #[test]
+    fn narrowing_bounds_the_loop_counter() {
+        let program = parser::parse("x := 0; while x < 10 do x := x + 1 enddo; skip ").unwrap();
+        let x = Variable::new("x");
+
+        for solver in [
+            algorithm::Solver::Mfp,
+            algorithm::Solver::Chaotic,
+            algorithm::Solver::RoundRobin,
+        ] {
+            let ana = solver.run(&program, &Intervals::new(&program));
+            assert_eq!(ana.entry[&2][&x], range(0, 10), "{solver:?}");
+            assert_eq!(ana.entry[&3][&x], range(0, 9), "{solver:?}");
+            assert_eq!(ana.entry[&4][&x], range(10, 10), "{solver:?}");
+        }
+    }
+}
@@ -0,0 +1,141 @@
+#![allow(dead_code)]
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Sub,
+};
+
+use crate::{
+    analysis::{pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{
+        ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock, TestBlock, WriteBlock,
+    },
+    expression::{Label, Variable},
+    program::Program,
+};
+
+/// reading `*p` reads p as well as every variable p may point to according to `pt`
+pub fn gen_lv(block: Block, pt: &PointsTo) -> BTreeSet<Variable> {
+    match block {
+        Block::Assignment(AssignmentBlock { expr, .. }) => pt.reads(&expr),
+        Block::ArrayAssignment(ArrayAssignmentBlock { index, expr, .. }) => {
+            pt.reads(&index).union(&pt.reads(&expr)).cloned().collect()
+        }
+        Block::Store(StoreBlock { ptr, expr, .. }) => {
+            let mut vars = pt.reads(&expr);
+            vars.insert(ptr);
+            vars
+        }
+        Block::Test(TestBlock { expr, .. }) => pt.reads_bexp(&expr),
+        Block::Skip(_) | Block::Read(_) => [].into(),
+        Block::Write(WriteBlock { expr, .. }) => pt.reads(&expr),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .flat_map(|(_, expr)| pt.reads(expr))
+            .collect(),
+    }
+}
+
+/// an array assignment only overwrites a single element and the other elements may still be
+/// read later, so it does not kill the array (a weak update); neither does a store kill any
+/// of its [targets](PointsTo::targets)
+pub fn kill_lv(block: Block) -> BTreeSet<Variable> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, .. }) | Block::Read(ReadBlock { var, .. }) => {
+            [var].into()
+        }
+        Block::ArrayAssignment(_) | Block::Store(_) => [].into(),
+        Block::Test(TestBlock { .. }) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block.bindings().iter().map(|(var, _)| *var).collect(),
+    }
+}
+
+/// live-variable analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct LiveVariables {
+    points_to: PointsTo,
+}
+impl LiveVariables {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for LiveVariables {
+    type Lattice = BTreeSet<Variable>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.union(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_subset(b)
+    }
+
+    /// LVEntry(l) = (LVExit(l) \ kill_LV(B^l)) U gen_LV(B^l)
+    fn transfer(&self, block: &Block, lv_exit: &Self::Lattice) -> Self::Lattice {
+        lv_exit
+            .sub(&kill_lv(block.clone()))
+            .union(&gen_lv(block.clone(), &self.points_to))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type LVAnalysis = Analysis<BTreeSet<Variable>>;
+
+pub type LVExitAtLabel = BTreeSet<Variable>;
+pub type LVExit = BTreeMap<Label, BTreeSet<Variable>>;
+pub type LVEntryAtLabel = BTreeSet<Variable>;
+pub type LVEntry = BTreeMap<Label, BTreeSet<Variable>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, parser};
+
+    fn vars(names: &[&str]) -> BTreeSet<Variable> {
+        names.iter().map(|name| Variable::new(name)).collect()
+    }
+
+    #[test]
+    fn array_assignments_do_not_kill_the_array() {
+        let program = parser::parse("a[j] := 2; a[i] := 1; write a[j] ").unwrap();
+        let ana = algorithm::mfp::run(&program, &LiveVariables::new(&program));
+
+        // the element written at label 1 may still be read at label 3
+        assert_eq!(ana.entry[&3], vars(&["a", "j"]));
+        assert_eq!(ana.entry[&2], vars(&["a", "i", "j"]));
+        assert_eq!(ana.entry[&1], vars(&["a", "i", "j"]));
+    }
+
+    #[test]
+    fn reads_kill_and_writes_use() {
+        let program = parser::parse("read x; write x + y; read x; write 1 ").unwrap();
+        let ana = algorithm::mfp::run(&program, &LiveVariables::new(&program));
+
+        // FV(x + y) is live before the write, and x is not live before either read
+        assert_eq!(ana.entry[&2], vars(&["x", "y"]));
+        assert_eq!(ana.entry[&1], vars(&["y"]));
+        assert_eq!(ana.exit[&2], vars(&[]));
+        assert_eq!(ana.entry[&4], vars(&[]));
+        assert_eq!(kill_lv(program.at(3).unwrap()), vars(&["x"]));
+    }
+}
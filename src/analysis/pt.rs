@@ -0,0 +1,199 @@
+#![allow(dead_code)]
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use fmtastic::Superscript;
+
+use crate::{
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, StoreBlock},
+    expression::{AExp, BExp, Label, Variable},
+    program::Program,
+};
+
+/// an abstract location a pointer may point to
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Location {
+    /// a variable of the program, whose address is taken using `&x`
+    Variable(Variable),
+
+    /// all heap cells allocated by the `new` at a label
+    Heap(Label),
+}
+
+/// Andersen-style points-to analysis: every assignment `x := a` is turned into the constraint
+/// pt(x) ⊇ pt(a) (and every store `*p := a` into pt(l) ⊇ pt(a) for each l in pt(p)), which are
+/// solved for the whole program at once, regardless of the order the blocks are executed in
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct PointsTo {
+    sets: BTreeMap<Location, BTreeSet<Location>>,
+}
+impl PointsTo {
+    pub fn new(program: &Program) -> Self {
+        let blocks = program.blocks();
+
+        let mut pt = Self::default();
+        loop {
+            let mut next = pt.clone();
+            for block in &blocks {
+                next.constrain(block);
+            }
+
+            if next == pt {
+                return pt;
+            }
+            pt = next;
+        }
+    }
+
+    /// adds the locations that the values assigned by a block may point to
+    fn constrain(&mut self, block: &Block) {
+        let label = block.get_label();
+        let assigned: Vec<(Location, BTreeSet<Location>)> = match block {
+            Block::Assignment(AssignmentBlock { var, expr, .. })
+            | Block::ArrayAssignment(ArrayAssignmentBlock {
+                array: var, expr, ..
+            }) => vec![(Location::Variable(*var), self.eval(expr, label))],
+            Block::Store(StoreBlock { ptr, expr, .. }) => {
+                let value = self.eval(expr, label);
+                self.get(Location::Variable(*ptr))
+                    .into_iter()
+                    .map(|target| (target, value.clone()))
+                    .collect()
+            }
+            _ => block
+                .bindings()
+                .into_iter()
+                .map(|(var, expr)| (Location::Variable(var), self.eval(&expr, label)))
+                .collect(),
+        };
+
+        for (location, value) in assigned {
+            if !value.is_empty() {
+                self.sets.entry(location).or_default().extend(value);
+            }
+        }
+    }
+
+    /// pt(l), the locations stored at l may point to
+    pub fn get(&self, location: Location) -> BTreeSet<Location> {
+        self.sets.get(&location).cloned().unwrap_or_default()
+    }
+
+    /// the locations the value of an expression in the block at `label` may point to
+    pub fn eval(&self, expr: &AExp, label: Label) -> BTreeSet<Location> {
+        match expr {
+            AExp::Variable(var) => self.get(Location::Variable(*var)),
+            AExp::Number(_) => [].into(),
+            // pointer arithmetic stays within the object pointed to
+            AExp::ArithmeticOp(lhs, _, rhs) => [self.eval(lhs, label), self.eval(rhs, label)]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            AExp::ArrayElement(array, _) => self.get(Location::Variable(*array)),
+            AExp::AddressOf(var) => [Location::Variable(*var)].into(),
+            AExp::Deref(ptr) => self
+                .get(Location::Variable(*ptr))
+                .into_iter()
+                .flat_map(|target| self.get(target))
+                .collect(),
+            AExp::New => [Location::Heap(label)].into(),
+        }
+    }
+
+    /// the variables `*p` may refer to; a store `*p := a` may, but need not, assign each of
+    /// them, so it is a weak update: none of them is definitely overwritten, but any of them
+    /// may have changed
+    pub fn targets(&self, ptr: Variable) -> BTreeSet<Variable> {
+        self.get(Location::Variable(ptr))
+            .into_iter()
+            .filter_map(|target| match target {
+                Location::Variable(var) => Some(var),
+                Location::Heap(_) => None,
+            })
+            .collect()
+    }
+
+    /// the variables whose values are read when evaluating an expression, including those
+    /// read through pointers, but not those whose address is taken
+    pub fn reads(&self, expr: &AExp) -> BTreeSet<Variable> {
+        match expr {
+            AExp::Variable(var) => [*var].into(),
+            AExp::Number(_) | AExp::AddressOf(_) | AExp::New => [].into(),
+            AExp::ArithmeticOp(lhs, _, rhs) => {
+                self.reads(lhs).union(&self.reads(rhs)).cloned().collect()
+            }
+            AExp::ArrayElement(array, index) => {
+                let mut vars = self.reads(index);
+                vars.insert(*array);
+                vars
+            }
+            AExp::Deref(ptr) => {
+                let mut vars = self.targets(*ptr);
+                vars.insert(*ptr);
+                vars
+            }
+        }
+    }
+
+    /// see [`PointsTo::reads`]
+    pub fn reads_bexp(&self, expr: &BExp) -> BTreeSet<Variable> {
+        expr.aexps()
+            .into_iter()
+            .flat_map(|expr| self.reads(expr))
+            .collect()
+    }
+
+    /// every location that may point somewhere, together with the locations it may point to
+    pub fn sets(&self) -> &BTreeMap<Location, BTreeSet<Location>> {
+        &self.sets
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Variable(var) => write!(f, "{var}"),
+            Location::Heap(label) => write!(f, "new{}", Superscript(*label)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn var(name: &str) -> Location {
+        Location::Variable(Variable::new(name))
+    }
+
+    #[test]
+    fn points_to_sets() {
+        let program = parser::parse(
+            "a := &x; if c > 0 then p := &y else p := new endif; q := p; \
+             *q := a; *q := &z; r := *q ",
+        )
+        .unwrap();
+        let pt = PointsTo::new(&program);
+
+        // the address of a variable and the cells allocated by a `new`
+        assert_eq!(pt.get(var("a")), [var("x")].into());
+        assert_eq!(pt.get(var("p")), [var("y"), Location::Heap(4)].into());
+
+        // copying a pointer copies everything it may point to
+        assert_eq!(pt.get(var("q")), pt.get(var("p")));
+
+        // stores are weak updates: the values of both stores are kept, in every target
+        let stored = [var("x"), var("z")].into();
+        assert_eq!(pt.get(var("y")), stored);
+        assert_eq!(pt.get(Location::Heap(4)), stored);
+        assert_eq!(pt.get(var("r")), stored);
+
+        // only variables are targets, the heap is not tracked
+        assert_eq!(pt.targets(Variable::new("q")), [Variable::new("y")].into());
+        assert_eq!(pt.get(var("c")), [].into());
+    }
+}
@@ -0,0 +1,181 @@
+#![allow(dead_code)]
+use std::{collections::BTreeSet, ops::Sub};
+
+use crate::{
+    analysis::{pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock},
+    expression::{Label, Variable},
+    program::Program,
+};
+
+/// a definition of a variable at a label; `None` stands for `?`, i.e. the variable being
+/// uninitialized at the start of the program
+pub type Definition = (Variable, Option<Label>);
+
+/// a store defines every variable its pointer may point to according to `pt`
+pub fn gen_rd(block: Block, pt: &PointsTo) -> BTreeSet<Definition> {
+    match block {
+        Block::Assignment(AssignmentBlock { label, var, .. })
+        | Block::Read(ReadBlock { label, var }) => [(var, Some(label))].into(),
+        Block::ArrayAssignment(ArrayAssignmentBlock { label, array, .. }) => {
+            [(array, Some(label))].into()
+        }
+        Block::Store(StoreBlock { label, ptr, .. }) => pt
+            .targets(ptr)
+            .into_iter()
+            .map(|var| (var, Some(label)))
+            .collect(),
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .map(|(var, _)| (*var, Some(block.get_label())))
+            .collect(),
+    }
+}
+
+/// kill_RD(B^l) based on the set of all assignments in the program
+pub fn kill_rd(block: Block, assignments: &BTreeSet<Definition>) -> BTreeSet<Definition> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, .. }) | Block::Read(ReadBlock { var, .. }) => {
+            [(var, None)]
+                .into_iter()
+                .chain(assignments.iter().filter(|(x, _)| x == &var).cloned())
+                .collect()
+        }
+        // the other elements of the array keep their earlier definitions, and a store does not
+        // necessarily assign any particular variable its pointer may point to
+        Block::ArrayAssignment(_) | Block::Store(_) => [].into(),
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .flat_map(|(var, _)| {
+                [(*var, None)]
+                    .into_iter()
+                    .chain(assignments.iter().filter(move |(x, _)| x == var).cloned())
+            })
+            .collect(),
+    }
+}
+
+/// reaching-definitions analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct ReachingDefinitions {
+    /// the variables of the program, each initially defined at `?`
+    free_vars: BTreeSet<Variable>,
+
+    /// every definition (x, l) such that B^l is an assignment to (or read of) x in the program
+    assignments: BTreeSet<Definition>,
+
+    points_to: PointsTo,
+}
+impl ReachingDefinitions {
+    pub fn new(program: &Program) -> Self {
+        let points_to = PointsTo::new(program);
+        Self {
+            free_vars: program.free_vars(),
+            assignments: program
+                .blocks()
+                .into_iter()
+                .flat_map(|block| gen_rd(block, &points_to))
+                .collect(),
+            points_to,
+        }
+    }
+}
+
+impl MonotoneFramework for ReachingDefinitions {
+    type Lattice = BTreeSet<Definition>;
+
+    fn direction(&self) -> Direction {
+        Direction::Forward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    /// {(x, ?) | x in FV(S*)}
+    fn extremal_value(&self) -> Self::Lattice {
+        self.free_vars.iter().map(|var| (*var, None)).collect()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.union(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_subset(b)
+    }
+
+    /// RDExit(l) = (RDEntry(l) \ kill_RD(B^l)) U gen_RD(B^l)
+    fn transfer(&self, block: &Block, rd_entry: &Self::Lattice) -> Self::Lattice {
+        rd_entry
+            .sub(&kill_rd(block.clone(), &self.assignments))
+            .union(&gen_rd(block.clone(), &self.points_to))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type RDAnalysis = Analysis<BTreeSet<Definition>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, parser};
+
+    fn defs(defs: &[(&str, Option<Label>)]) -> BTreeSet<Definition> {
+        defs.iter()
+            .map(|(var, label)| (Variable::new(var), *label))
+            .collect()
+    }
+
+    #[test]
+    fn variables_are_initially_undefined() {
+        let program = parser::parse("x := 5; y := x ").unwrap();
+        let rd = ReachingDefinitions::new(&program);
+
+        assert_eq!(rd.extremal_value(), defs(&[("x", None), ("y", None)]));
+    }
+
+    #[test]
+    fn assignments_kill_all_other_definitions() {
+        let program = parser::parse("x := 5; read x; x := x - 1 ").unwrap();
+        let rd = ReachingDefinitions::new(&program);
+        let assignment = program.at(3).unwrap();
+
+        assert_eq!(
+            gen_rd(assignment.clone(), &rd.points_to),
+            defs(&[("x", Some(3))])
+        );
+        assert_eq!(
+            kill_rd(assignment, &rd.assignments),
+            defs(&[("x", None), ("x", Some(1)), ("x", Some(2)), ("x", Some(3))])
+        );
+    }
+
+    #[test]
+    fn definitions_reach_around_loops() {
+        let program =
+            parser::parse("x := 5; y := 1; while x > 1 do y := x * y; x := x - 1 enddo ").unwrap();
+        let ana = algorithm::mfp::run(&program, &ReachingDefinitions::new(&program));
+
+        assert_eq!(ana.entry[&1], defs(&[("x", None), ("y", None)]));
+        assert_eq!(
+            ana.entry[&3],
+            defs(&[
+                ("x", Some(1)),
+                ("x", Some(5)),
+                ("y", Some(2)),
+                ("y", Some(4))
+            ])
+        );
+        assert_eq!(ana.exit[&5], defs(&[("x", Some(5)), ("y", Some(4))]));
+    }
+}
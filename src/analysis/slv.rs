@@ -0,0 +1,109 @@
+#![allow(dead_code)]
+use std::{collections::BTreeSet, ops::Sub};
+
+use crate::{
+    analysis::{
+        lv::kill_lv,
+        pt::{Location, PointsTo},
+        Analysis, Direction, MonotoneFramework,
+    },
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, StoreBlock, TestBlock, WriteBlock},
+    expression::Variable,
+    program::Program,
+};
+
+/// unlike gen_LV, an assignment only generates the variables of its right-hand side if the
+/// variable it assigns to is strongly live afterwards; a store does so if any variable it may
+/// assign to is, or if it may assign to the heap, which is not tracked; the value of a write
+/// is always used
+pub fn gen_slv(block: Block, slv_exit: &BTreeSet<Variable>, pt: &PointsTo) -> BTreeSet<Variable> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, expr, .. }) => {
+            if slv_exit.contains(&var) {
+                pt.reads(&expr)
+            } else {
+                [].into()
+            }
+        }
+        Block::ArrayAssignment(ArrayAssignmentBlock {
+            array, index, expr, ..
+        }) => {
+            if slv_exit.contains(&array) {
+                pt.reads(&index).union(&pt.reads(&expr)).cloned().collect()
+            } else {
+                [].into()
+            }
+        }
+        Block::Store(StoreBlock { ptr, expr, .. }) => {
+            let live = |target: &Location| match target {
+                Location::Variable(var) => slv_exit.contains(var),
+                Location::Heap(_) => true,
+            };
+            if pt.get(Location::Variable(ptr)).iter().any(live) {
+                let mut vars = pt.reads(&expr);
+                vars.insert(ptr);
+                vars
+            } else {
+                [].into()
+            }
+        }
+        Block::Test(TestBlock { expr, .. }) => pt.reads_bexp(&expr),
+        Block::Write(WriteBlock { expr, .. }) => pt.reads(&expr),
+        Block::Skip(_) | Block::Read(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .filter(|(var, _)| slv_exit.contains(var))
+            .flat_map(|(_, expr)| pt.reads(expr))
+            .collect(),
+    }
+}
+
+/// strongly-live-variables (faint-variables) analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct StronglyLiveVariables {
+    points_to: PointsTo,
+}
+impl StronglyLiveVariables {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for StronglyLiveVariables {
+    type Lattice = BTreeSet<Variable>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.union(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_subset(b)
+    }
+
+    /// SLVEntry(l) = (SLVExit(l) \ kill_LV(B^l)) U gen_SLV(B^l, SLVExit(l))
+    fn transfer(&self, block: &Block, slv_exit: &Self::Lattice) -> Self::Lattice {
+        slv_exit
+            .sub(&kill_lv(block.clone()))
+            .union(&gen_slv(block.clone(), slv_exit, &self.points_to))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type SLVAnalysis = Analysis<BTreeSet<Variable>>;
@@ -0,0 +1,117 @@
+#![allow(dead_code)]
+use std::{collections::BTreeSet, ops::Sub};
+
+use crate::{
+    analysis::{pt::PointsTo, Analysis, Direction, MonotoneFramework},
+    block::{
+        ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock, TestBlock, WriteBlock,
+    },
+    expression::AExp,
+    program::Program,
+};
+
+pub fn gen_vb(block: Block) -> BTreeSet<AExp> {
+    match block {
+        Block::Assignment(AssignmentBlock { expr, .. }) => expr.subexpressions(),
+        Block::ArrayAssignment(ArrayAssignmentBlock { index, expr, .. }) => index
+            .subexpressions()
+            .union(&expr.subexpressions())
+            .cloned()
+            .collect(),
+        Block::Store(StoreBlock { expr, .. }) | Block::Write(WriteBlock { expr, .. }) => {
+            expr.subexpressions()
+        }
+        Block::Test(TestBlock { expr, .. }) => expr.subexpressions(),
+        Block::Skip(_) | Block::Read(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .flat_map(|(_, expr)| expr.subexpressions())
+            .collect(),
+    }
+}
+
+/// kill_VB(B^l) based on the set of all non-trivial arithmetic expressions AExp*
+pub fn kill_vb(block: Block, aexp_star: &BTreeSet<AExp>, pt: &PointsTo) -> BTreeSet<AExp> {
+    match block {
+        Block::Assignment(AssignmentBlock { var, .. })
+        | Block::ArrayAssignment(ArrayAssignmentBlock { array: var, .. })
+        | Block::Read(ReadBlock { var, .. }) => aexp_star
+            .iter()
+            .filter(|a| a.contains_var(&var))
+            .cloned()
+            .collect(),
+        Block::Store(StoreBlock { ptr, .. }) => {
+            let targets = pt.targets(ptr);
+            aexp_star
+                .iter()
+                .filter(|a| targets.iter().any(|var| a.contains_var(var)))
+                .cloned()
+                .collect()
+        }
+        Block::Test(_) => [].into(),
+        Block::Skip(_) | Block::Write(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => {
+            let bindings = block.bindings();
+            aexp_star
+                .iter()
+                .filter(|a| bindings.iter().any(|(var, _)| a.contains_var(var)))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+/// very-busy-expressions analysis as an instance of the monotone framework
+#[derive(Clone, Debug)]
+pub struct VeryBusyExpressions {
+    /// AExp*, the non-trivial arithmetic expressions of the program
+    aexp_star: BTreeSet<AExp>,
+
+    points_to: PointsTo,
+}
+impl VeryBusyExpressions {
+    pub fn new(program: &Program) -> Self {
+        Self {
+            aexp_star: program.aexp_star(),
+            points_to: PointsTo::new(program),
+        }
+    }
+}
+
+impl MonotoneFramework for VeryBusyExpressions {
+    type Lattice = BTreeSet<AExp>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn bottom(&self) -> Self::Lattice {
+        self.aexp_star.clone()
+    }
+
+    fn extremal_value(&self) -> Self::Lattice {
+        [].into()
+    }
+
+    fn join(&self, a: &Self::Lattice, b: &Self::Lattice) -> Self::Lattice {
+        a.intersection(b).cloned().collect()
+    }
+
+    fn leq(&self, a: &Self::Lattice, b: &Self::Lattice) -> bool {
+        a.is_superset(b)
+    }
+
+    /// VBEntry(l) = (VBExit(l) \ kill_VB(B^l)) U gen_VB(B^l)
+    fn transfer(&self, block: &Block, vb_exit: &Self::Lattice) -> Self::Lattice {
+        vb_exit
+            .sub(&kill_vb(block.clone(), &self.aexp_star, &self.points_to))
+            .union(&gen_vb(block.clone()))
+            .cloned()
+            .collect()
+    }
+}
+
+pub type VBAnalysis = Analysis<BTreeSet<AExp>>;
@@ -1,75 +1,264 @@
 #![allow(dead_code)]
-use std::fmt::Display;
+use std::{collections::BTreeSet, fmt::Display};
 
-use fmtastic::Superscript;
+use fmtastic::{Subscript, Superscript};
 
 use crate::expression::{AExp, BExp, Label, Variable};
 
 /// represents a single statement in a program
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Block {
     Assignment(AssignmentBlock),
+    /// an assignment to a single element of an array
+    ArrayAssignment(ArrayAssignmentBlock),
+    /// an assignment to the location a pointer points to
+    Store(StoreBlock),
     Skip(SkipBlock),
+    /// an assignment of a value read from the input
+    Read(ReadBlock),
+    /// an output of the value of an expression
+    Write(WriteBlock),
     Test(TestBlock),
+    /// `is` of a procedure declaration, where its body is entered
+    Entry(ProcBlock),
+    /// `end` of a procedure declaration, where its body is left
+    Exit(ProcBlock),
+    /// a procedure call at its call label
+    Call(CallBlock),
+    /// a procedure call at its return label
+    Return(CallBlock),
 }
 impl Block {
     pub fn get_label(&self) -> Label {
         match self {
             Self::Assignment(b) => b.label,
+            Self::ArrayAssignment(b) => b.label,
+            Self::Store(b) => b.label,
             Self::Skip(b) => b.label,
+            Self::Read(b) => b.label,
+            Self::Write(b) => b.label,
             Self::Test(b) => b.label,
+            Self::Entry(b) | Self::Exit(b) => b.label,
+            Self::Call(b) => b.label,
+            Self::Return(b) => b.ret,
+        }
+    }
+
+    /// the variables assigned by a block together with the expressions assigned to them; the
+    /// parameters of a call are passed as simultaneous assignments, the value parameters at the
+    /// call label and the result parameters at the return label; arrays are only ever assigned
+    /// element by element and stores assign whatever their pointer points to, so neither
+    /// of them has any, and neither does `read x`, whose value is not known
+    pub fn bindings(&self) -> Vec<(Variable, AExp)> {
+        match self {
+            Self::Assignment(AssignmentBlock { var, expr, .. }) => vec![(*var, expr.clone())],
+            Self::Call(call) => call.val.iter().copied().zip(call.args.clone()).collect(),
+            Self::Return(call) => call
+                .results
+                .iter()
+                .zip(&call.res)
+                .map(|(z, y)| (*z, AExp::Variable(*y)))
+                .collect(),
+            Self::ArrayAssignment(_)
+            | Self::Store(_)
+            | Self::Skip(_)
+            | Self::Read(_)
+            | Self::Write(_)
+            | Self::Test(_)
+            | Self::Entry(_)
+            | Self::Exit(_) => vec![],
         }
     }
 
     pub fn assignment(label: Label, var: Variable, expr: AExp) -> Self {
         Self::Assignment(AssignmentBlock { label, var, expr })
     }
+    pub fn array_assignment(label: Label, array: Variable, index: AExp, expr: AExp) -> Self {
+        Self::ArrayAssignment(ArrayAssignmentBlock {
+            label,
+            array,
+            index,
+            expr,
+        })
+    }
+    pub fn store(label: Label, ptr: Variable, expr: AExp) -> Self {
+        Self::Store(StoreBlock { label, ptr, expr })
+    }
     pub fn skip(label: Label) -> Self {
         Self::Skip(SkipBlock { label })
     }
+    pub fn read(label: Label, var: Variable) -> Self {
+        Self::Read(ReadBlock { label, var })
+    }
+    pub fn write(label: Label, expr: AExp) -> Self {
+        Self::Write(WriteBlock { label, expr })
+    }
     pub fn test(label: Label, expr: BExp) -> Self {
         Self::Test(TestBlock { label, expr })
     }
 }
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct AssignmentBlock {
     pub label: Label,
     pub var: Variable,
     pub expr: AExp,
 }
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+/// \[A\[i\] := a\], which updates the element of `A` at index `i` and leaves the others as
+/// they were
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ArrayAssignmentBlock {
+    pub label: Label,
+    pub array: Variable,
+    pub index: AExp,
+    pub expr: AExp,
+}
+
+/// \[*p := a\], which assigns to the location `p` points to
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct StoreBlock {
+    pub label: Label,
+    pub ptr: Variable,
+    pub expr: AExp,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct SkipBlock {
     pub label: Label,
 }
 
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+/// \[read x\], which assigns a value from the input to `x`
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ReadBlock {
+    pub label: Label,
+    pub var: Variable,
+}
+
+/// \[write a\], which outputs the value of `a`
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct WriteBlock {
+    pub label: Label,
+    pub expr: AExp,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct TestBlock {
     pub label: Label,
     pub expr: BExp,
 }
 
-impl Display for Block {
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ProcBlock {
+    pub label: Label,
+    pub name: String,
+}
+
+/// \[call p(a, z)\] with call label `label` and return label `ret`; the parser only fills in
+/// the name and the arguments, the rest is resolved against the declaration of the procedure
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CallBlock {
+    pub label: Label,
+    pub ret: Label,
+    pub name: String,
+    /// the arguments passed to the value parameters
+    pub args: Vec<AExp>,
+    /// the variables receiving the result parameters
+    pub results: Vec<Variable>,
+
+    /// the entry label of the procedure
+    pub entry: Label,
+    /// the exit label of the procedure
+    pub exit: Label,
+    /// the value parameters of the procedure
+    pub val: Vec<Variable>,
+    /// the result parameters of the procedure
+    pub res: Vec<Variable>,
+}
+impl CallBlock {
+    pub fn new(name: String, args: Vec<AExp>) -> Self {
+        Self {
+            label: 0,
+            ret: 0,
+            name,
+            args,
+            results: vec![],
+            entry: 0,
+            exit: 0,
+            val: vec![],
+            res: vec![],
+        }
+    }
+
+    /// the formal parameters of the procedure, which are local to its body
+    pub fn locals(&self) -> BTreeSet<Variable> {
+        self.val.iter().chain(&self.res).copied().collect()
+    }
+}
+
+impl Display for CallBlock {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(AExp::to_string)
+            .chain(self.results.iter().map(Variable::to_string))
+            .collect();
         write!(
             f,
-            "[{}]{}",
-            match self {
-                Block::Assignment(AssignmentBlock { var, expr, .. }) => {
-                    [
-                        var.to_string().as_str(),
-                        " := ",
-                        format!("{}", expr).as_str(),
-                    ]
-                    .concat()
-                }
-
-                Block::Skip(_) => "skip".to_string(),
-
-                Block::Test(TestBlock { expr, .. }) => format!("{}", expr),
-            },
-            Superscript(self.get_label())
+            "[call {}({})]{}{}",
+            self.name,
+            args.join(", "),
+            Superscript(self.label),
+            Subscript(self.ret)
         )
     }
 }
+
+impl Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Block::Assignment(AssignmentBlock { label, var, expr }) => {
+                write!(f, "[{} := {}]{}", var, expr, Superscript(*label))
+            }
+
+            Block::ArrayAssignment(ArrayAssignmentBlock {
+                label,
+                array,
+                index,
+                expr,
+            }) => write!(
+                f,
+                "[{}[{}] := {}]{}",
+                array,
+                index,
+                expr,
+                Superscript(*label)
+            ),
+
+            Block::Store(StoreBlock { label, ptr, expr }) => {
+                write!(f, "[*{} := {}]{}", ptr, expr, Superscript(*label))
+            }
+
+            Block::Skip(SkipBlock { label }) => write!(f, "[skip]{}", Superscript(*label)),
+
+            Block::Read(ReadBlock { label, var }) => {
+                write!(f, "[read {}]{}", var, Superscript(*label))
+            }
+
+            Block::Write(WriteBlock { label, expr }) => {
+                write!(f, "[write {}]{}", expr, Superscript(*label))
+            }
+
+            Block::Test(TestBlock { label, expr }) => {
+                write!(f, "[{}]{}", expr, Superscript(*label))
+            }
+
+            Block::Entry(ProcBlock { label, .. }) => write!(f, "is{}", Superscript(*label)),
+
+            Block::Exit(ProcBlock { label, .. }) => write!(f, "end{}", Superscript(*label)),
+
+            Block::Call(call) | Block::Return(call) => call.fmt(f),
+        }
+    }
+}
@@ -1,12 +1,61 @@
 #![allow(dead_code)]
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Display},
+    sync::{Mutex, OnceLock},
+};
 
 pub type Label = usize; // label index
-pub type Variable = char; // variable index
 pub type Value = i32; // an actual numeric value (only for displaying)
 
+/// the name of a variable; names are interned, so variables are cheap to copy and compare
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Variable(&'static str);
+impl Variable {
+    pub fn new(name: &str) -> Self {
+        static NAMES: OnceLock<Mutex<BTreeSet<&'static str>>> = OnceLock::new();
+
+        let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+        match names.get(name) {
+            Some(name) => Self(name),
+            None => {
+                let name: &'static str = Box::leak(name.into());
+                names.insert(name);
+                Self(name)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// hands out variables that do not occur in the program yet, e.g. for temporaries
+#[derive(Clone, Debug)]
+pub struct FreshVariables {
+    used: BTreeSet<Variable>,
+}
+impl FreshVariables {
+    pub fn new(used: BTreeSet<Variable>) -> Self {
+        Self { used }
+    }
+}
+impl Iterator for FreshVariables {
+    type Item = Variable;
+
+    /// returns the first of `t1`, `t2`, ... that is not in use yet
+    fn next(&mut self) -> Option<Variable> {
+        let var = (1..)
+            .map(|i| Variable::new(&format!("t{i}")))
+            .find(|var| !self.used.contains(var))?;
+        self.used.insert(var);
+        Some(var)
+    }
+}
+
 /// represents an arithmetic expression as it may appear in an assignment to a variable
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum AExp {
     // the index of a variable
     Variable(Variable),
@@ -16,23 +65,123 @@ pub enum AExp {
 
     // + - * /; operator is irrelevant
     ArithmeticOp(Box<AExp>, String, Box<AExp>),
+
+    // an element A[e] of an array, read at the given index
+    ArrayElement(Variable, Box<AExp>),
+
+    // the address &x of a variable
+    AddressOf(Variable),
+
+    // the value *p stored at the location a pointer points to
+    Deref(Variable),
+
+    // the address of a newly allocated heap cell
+    New,
 }
 impl AExp {
-    pub fn free_vars(&self) -> HashSet<Variable> {
+    pub fn free_vars(&self) -> BTreeSet<Variable> {
         match self {
-            AExp::Variable(var) => [var.clone()].into(),
+            AExp::Variable(var) => [*var].into(),
             AExp::Number(_) => [].into(),
             AExp::ArithmeticOp(lhs, _, rhs) => [lhs.free_vars(), rhs.free_vars()]
                 .iter()
                 .flatten()
                 .cloned()
                 .collect(),
+            AExp::ArrayElement(array, index) => {
+                let mut vars = index.free_vars();
+                vars.insert(*array);
+                vars
+            }
+            AExp::AddressOf(var) | AExp::Deref(var) => [*var].into(),
+            AExp::New => [].into(),
+        }
+    }
+
+    /// the pointers dereferenced in the expression
+    pub fn dereferenced(&self) -> BTreeSet<Variable> {
+        match self {
+            AExp::Deref(ptr) => [*ptr].into(),
+            AExp::ArithmeticOp(lhs, _, rhs) => [lhs.dereferenced(), rhs.dereferenced()]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            AExp::ArrayElement(_, index) => index.dereferenced(),
+            AExp::Variable(_) | AExp::Number(_) | AExp::AddressOf(_) | AExp::New => [].into(),
+        }
+    }
+
+    pub fn contains_var(&self, var: &Variable) -> bool {
+        self.free_vars().contains(var)
+    }
+
+    /// replaces every variable for which `f` returns an expression by that expression; the
+    /// names of arrays and the variables whose address is taken are left alone, and
+    /// dereferenced pointers are only replaced by other variables
+    pub fn substitute(&self, f: &impl Fn(&Variable) -> Option<AExp>) -> AExp {
+        match self {
+            AExp::Variable(var) => f(var).unwrap_or(self.clone()),
+            AExp::Number(_) => self.clone(),
+            AExp::ArithmeticOp(lhs, op, rhs) => AExp::ArithmeticOp(
+                Box::new(lhs.substitute(f)),
+                op.clone(),
+                Box::new(rhs.substitute(f)),
+            ),
+            AExp::ArrayElement(array, index) => {
+                AExp::ArrayElement(*array, Box::new(index.substitute(f)))
+            }
+            AExp::Deref(ptr) => match f(ptr) {
+                Some(AExp::Variable(var)) => AExp::Deref(var),
+                _ => self.clone(),
+            },
+            AExp::AddressOf(_) | AExp::New => self.clone(),
+        }
+    }
+
+    /// the number of nodes in the expression tree
+    pub fn size(&self) -> usize {
+        match self {
+            AExp::Variable(_)
+            | AExp::Number(_)
+            | AExp::AddressOf(_)
+            | AExp::Deref(_)
+            | AExp::New => 1,
+            AExp::ArithmeticOp(lhs, _, rhs) => 1 + lhs.size() + rhs.size(),
+            AExp::ArrayElement(_, index) => 1 + index.size(),
+        }
+    }
+
+    /// returns the non-trivial arithmetic subexpressions of the expression, including itself;
+    /// reading an array element does not count as one, but its index may contain some;
+    /// expressions reading through a pointer are left out, as any assignment may change them
+    pub fn subexpressions(&self) -> BTreeSet<AExp> {
+        match self {
+            AExp::Variable(_) | AExp::Number(_) => [].into(),
+            AExp::AddressOf(_) | AExp::Deref(_) | AExp::New => [].into(),
+            AExp::ArrayElement(_, index) => index.subexpressions(),
+            AExp::ArithmeticOp(lhs, _, rhs) if !self.dereferenced().is_empty() => {
+                [lhs.subexpressions(), rhs.subexpressions()]
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect()
+            }
+            AExp::ArithmeticOp(lhs, _, rhs) => [
+                [self.clone()].into(),
+                lhs.subexpressions(),
+                rhs.subexpressions(),
+            ]
+            .iter()
+            .flatten()
+            .cloned()
+            .collect(),
         }
     }
 }
 
 /// represents a boolean expression as it may appear (by itself) in a block
-#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum BExp {
     True,
     False,
@@ -46,7 +195,7 @@ pub enum BExp {
     RelationalOp(AExp, String, AExp),
 }
 impl BExp {
-    pub fn free_vars(&self) -> HashSet<Variable> {
+    pub fn free_vars(&self) -> BTreeSet<Variable> {
         match self {
             BExp::True | BExp::False => [].into(),
             BExp::Not(inner) => inner.free_vars(),
@@ -62,6 +211,98 @@ impl BExp {
                 .collect(),
         }
     }
+
+    /// the arithmetic expressions compared in the expression
+    pub fn aexps(&self) -> Vec<&AExp> {
+        match self {
+            BExp::True | BExp::False => vec![],
+            BExp::Not(inner) => inner.aexps(),
+            BExp::BooleanOp(lhs, _, rhs) => [lhs.aexps(), rhs.aexps()].concat(),
+            BExp::RelationalOp(lhs, _, rhs) => vec![lhs, rhs],
+        }
+    }
+
+    /// applies `f` to every arithmetic expression compared in the expression
+    pub fn map_aexps(&self, f: &impl Fn(&AExp) -> AExp) -> BExp {
+        match self {
+            BExp::True | BExp::False => self.clone(),
+            BExp::Not(inner) => BExp::Not(Box::new(inner.map_aexps(f))),
+            BExp::BooleanOp(lhs, op, rhs) => BExp::BooleanOp(
+                Box::new(lhs.map_aexps(f)),
+                op.clone(),
+                Box::new(rhs.map_aexps(f)),
+            ),
+            BExp::RelationalOp(lhs, op, rhs) => BExp::RelationalOp(f(lhs), op.clone(), f(rhs)),
+        }
+    }
+
+    /// replaces every variable for which `f` returns an expression by that expression
+    pub fn substitute(&self, f: &impl Fn(&Variable) -> Option<AExp>) -> BExp {
+        self.map_aexps(&|expr| expr.substitute(f))
+    }
+
+    /// returns the non-trivial arithmetic subexpressions of the expression
+    pub fn subexpressions(&self) -> BTreeSet<AExp> {
+        match self {
+            BExp::True | BExp::False => [].into(),
+            BExp::Not(inner) => inner.subexpressions(),
+            BExp::BooleanOp(lhs, _, rhs) => [lhs.subexpressions(), rhs.subexpressions()]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            BExp::RelationalOp(lhs, _, rhs) => [lhs.subexpressions(), rhs.subexpressions()]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// applies one of the operators `+ - * /`; `None` if the result is not representable
+pub fn eval_arithmetic_op(lhs: Value, op: &str, rhs: Value) -> Option<Value> {
+    match op {
+        "+" => lhs.checked_add(rhs),
+        "-" => lhs.checked_sub(rhs),
+        "*" => lhs.checked_mul(rhs),
+        "/" => lhs.checked_div(rhs),
+        _ => unreachable!("unknown arithmetic operator '{op}'"),
+    }
+}
+
+/// applies one of the operators `< <= == != >= >`
+pub fn eval_relational_op(lhs: Value, op: &str, rhs: Value) -> bool {
+    match op {
+        "<" => lhs < rhs,
+        "<=" => lhs <= rhs,
+        "==" => lhs == rhs,
+        "!=" => lhs != rhs,
+        ">=" => lhs >= rhs,
+        ">" => lhs > rhs,
+        _ => unreachable!("unknown relational operator '{op}'"),
+    }
+}
+
+/// applies one of the operators `&& ||`
+pub fn eval_boolean_op(lhs: bool, op: &str, rhs: bool) -> bool {
+    match op {
+        "&&" => lhs && rhs,
+        "||" => lhs || rhs,
+        _ => unreachable!("unknown boolean operator '{op}'"),
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Debug for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
 }
 
 impl Display for AExp {
@@ -76,6 +317,14 @@ impl Display for AExp {
 
                 AExp::ArithmeticOp(lhs, op, rhs) =>
                     [lhs.to_string(), op.to_string(), rhs.to_string()].concat(),
+
+                AExp::ArrayElement(array, index) => format!("{array}[{index}]"),
+
+                AExp::AddressOf(var) => format!("&{var}"),
+
+                AExp::Deref(ptr) => format!("*{ptr}"),
+
+                AExp::New => "new".to_string(),
             }
         )
     }
@@ -114,3 +363,17 @@ impl Display for BExp {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_variables_skip_names_in_use() {
+        let used = ["t1", "t2", "x"].map(Variable::new).into();
+        let mut fresh = FreshVariables::new(used);
+
+        assert_eq!(fresh.next(), Some(Variable::new("t3")));
+        assert_eq!(fresh.next(), Some(Variable::new("t4")));
+    }
+}
@@ -1,9 +1,16 @@
 #![allow(dead_code)]
 
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
-use crate::{block::Block, expression::Label, statement::Statement};
+use crate::{
+    block::{
+        ArrayAssignmentBlock, AssignmentBlock, Block, ReadBlock, StoreBlock, TestBlock, WriteBlock,
+    },
+    expression::{AExp, Label, Variable},
+    statement::{Procedure, Statement},
+};
 
+/// init(S): the label at which execution of a statement starts
 pub fn init_label(stmt: &Statement) -> Label {
     use crate::statement::Statement::*;
     match stmt {
@@ -14,10 +21,13 @@ pub fn init_label(stmt: &Statement) -> Label {
         IfThenElse(test, _, _) => test.label,
 
         While(test, _) => test.label,
+
+        Call(call) => call.label,
     }
 }
 
-pub fn final_labels(stmt: &Statement) -> HashSet<Label> {
+/// final(S): the labels at which execution of a statement may end
+pub fn final_labels(stmt: &Statement) -> BTreeSet<Label> {
     use crate::statement::Statement::*;
     match stmt {
         Atom(block) => [block.get_label()].into(),
@@ -30,16 +40,19 @@ pub fn final_labels(stmt: &Statement) -> HashSet<Label> {
             .collect(),
 
         While(test, _) => [test.label].into(),
+
+        Call(call) => [call.ret].into(),
     }
 }
 
-pub fn blocks(stmt: &Statement) -> HashSet<Block> {
+/// blocks(S): the elementary blocks of a statement
+pub fn blocks(stmt: &Statement) -> BTreeSet<Block> {
     use crate::statement::Statement::*;
     match stmt {
-        // pad with empty sets so that all match arms have the return type [HashSet<(Label, Label)>; 3]
-        Atom(block) => [[block.clone()].into(), HashSet::new(), HashSet::new()],
+        // pad with empty sets so that all match arms have the return type [BTreeSet<(Label, Label)>; 3]
+        Atom(block) => [[block.clone()].into(), BTreeSet::new(), BTreeSet::new()],
 
-        Sequence(stmt1, stmt2) => [blocks(stmt1), blocks(stmt2), HashSet::new()],
+        Sequence(stmt1, stmt2) => [blocks(stmt1), blocks(stmt2), BTreeSet::new()],
 
         IfThenElse(test, stmt1, stmt2) => [
             [Block::Test(test.clone())].into(),
@@ -50,7 +63,13 @@ pub fn blocks(stmt: &Statement) -> HashSet<Block> {
         While(test, stmt1) => [
             [Block::Test(test.clone())].into(),
             blocks(stmt1),
-            HashSet::new(),
+            BTreeSet::new(),
+        ],
+
+        Call(call) => [
+            [Block::Call(call.clone()), Block::Return(call.clone())].into(),
+            BTreeSet::new(),
+            BTreeSet::new(),
         ],
     }
     .iter()
@@ -59,11 +78,12 @@ pub fn blocks(stmt: &Statement) -> HashSet<Block> {
     .collect()
 }
 
-pub fn flow(stmt: &Statement) -> HashSet<(Label, Label)> {
+/// flow(S): the edges of the control flow graph of a statement
+pub fn flow(stmt: &Statement) -> BTreeSet<(Label, Label)> {
     use crate::statement::Statement::*;
     match stmt {
-        // pad with empty sets so that all match arms have the return type [HashSet<(Label, Label)>; 3]
-        Atom(_) => [HashSet::new(), HashSet::new(), HashSet::new()],
+        // pad with empty sets so that all match arms have the return type [BTreeSet<(Label, Label)>; 3]
+        Atom(_) => [BTreeSet::new(), BTreeSet::new(), BTreeSet::new()],
 
         Sequence(stmt1, stmt2) => [
             // flow(S1) U flow(S2) ...
@@ -72,16 +92,16 @@ pub fn flow(stmt: &Statement) -> HashSet<(Label, Label)> {
             // ... U {(l, init(S2)) | l in final(S1)}
             final_labels(stmt1)
                 .iter()
-                .map(|stmt1_final| (stmt1_final.clone(), init_label(stmt2)))
+                .map(|stmt1_final| (*stmt1_final, init_label(stmt2)))
                 .collect(),
         ],
 
         IfThenElse(test, stmt1, stmt2) => [
             // flow(S1) U flow(S2) ...
-            flow(&stmt1),
-            flow(&stmt2),
+            flow(stmt1),
+            flow(stmt2),
             // ... U {(l, init(S1)), (l, init(S2))}
-            HashSet::from([
+            BTreeSet::from([
                 (test.label, init_label(stmt1)),
                 (test.label, init_label(stmt2)),
             ]),
@@ -95,9 +115,16 @@ pub fn flow(stmt: &Statement) -> HashSet<(Label, Label)> {
             // ... U {(l, init(S2)) | l in final(S1)}
             final_labels(stmt1)
                 .iter()
-                .map(|stmt1_final| (stmt1_final.clone(), test.label))
+                .map(|stmt1_final| (*stmt1_final, test.label))
                 .collect(),
         ],
+
+        Call(call) => [
+            // {(lc; ln), (lx; lr)}
+            [(call.label, call.entry), (call.exit, call.ret)].into(),
+            BTreeSet::new(),
+            BTreeSet::new(),
+        ],
     }
     .iter()
     .flatten()
@@ -105,6 +132,158 @@ pub fn flow(stmt: &Statement) -> HashSet<(Label, Label)> {
     .collect()
 }
 
-pub fn flow_r(stmt: &Statement) -> HashSet<(Label, Label)> {
+/// the edges of `flow` that are taken when the test at their source evaluates to true
+pub fn true_flow(stmt: &Statement) -> BTreeSet<(Label, Label)> {
+    use crate::statement::Statement::*;
+    match stmt {
+        Atom(_) | Call(_) => BTreeSet::new(),
+
+        Sequence(stmt1, stmt2) => true_flow(stmt1).union(&true_flow(stmt2)).cloned().collect(),
+
+        IfThenElse(test, stmt1, stmt2) => [
+            [(test.label, init_label(stmt1))].into(),
+            true_flow(stmt1),
+            true_flow(stmt2),
+        ]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect(),
+
+        While(test, stmt1) => [(test.label, init_label(stmt1))]
+            .into_iter()
+            .chain(true_flow(stmt1))
+            .collect(),
+    }
+}
+
+/// the labels of the tests of all while loops
+pub fn loop_heads(stmt: &Statement) -> BTreeSet<Label> {
+    use crate::statement::Statement::*;
+    match stmt {
+        Atom(_) | Call(_) => BTreeSet::new(),
+
+        Sequence(stmt1, stmt2) | IfThenElse(_, stmt1, stmt2) => loop_heads(stmt1)
+            .union(&loop_heads(stmt2))
+            .cloned()
+            .collect(),
+
+        While(test, stmt1) => [test.label].into_iter().chain(loop_heads(stmt1)).collect(),
+    }
+}
+
+/// inter-flow(S): the tuples (lc, ln, lx, lr) of the calls in a statement, relating the call
+/// and return labels of each call to the entry and exit labels of the procedure it calls
+pub fn inter_flow(stmt: &Statement) -> BTreeSet<(Label, Label, Label, Label)> {
+    use crate::statement::Statement::*;
+    match stmt {
+        Atom(_) => BTreeSet::new(),
+
+        Sequence(stmt1, stmt2) | IfThenElse(_, stmt1, stmt2) => inter_flow(stmt1)
+            .union(&inter_flow(stmt2))
+            .cloned()
+            .collect(),
+
+        While(_, stmt1) => inter_flow(stmt1),
+
+        Call(call) => [(call.label, call.entry, call.exit, call.ret)].into(),
+    }
+}
+
+/// blocks(D): the blocks of a procedure declaration, including `is` and `end`
+pub fn proc_blocks(proc: &Procedure) -> BTreeSet<Block> {
+    [proc.entry_block(), proc.exit_block()]
+        .into_iter()
+        .chain(blocks(&proc.body))
+        .collect()
+}
+
+/// flow(D): the edges of the control flow graph of a procedure declaration
+pub fn proc_flow(proc: &Procedure) -> BTreeSet<(Label, Label)> {
+    [
+        // {(ln, init(S))} ...
+        [(proc.entry, init_label(&proc.body))].into(),
+        // ... U flow(S) ...
+        flow(&proc.body),
+        // ... U {(l, lx) | l in final(S)}
+        final_labels(&proc.body)
+            .iter()
+            .map(|body_final| (*body_final, proc.exit))
+            .collect(),
+    ]
+    .iter()
+    .flatten()
+    .cloned()
+    .collect()
+}
+
+/// flowᴿ(S): the edges of the control flow graph in reverse
+pub fn flow_r(stmt: &Statement) -> BTreeSet<(Label, Label)> {
     flow(stmt).iter().map(|x| (x.1, x.0)).collect()
 }
+
+/// FV(S): the variables occurring in a statement
+pub fn free_vars(stmt: &Statement) -> BTreeSet<Variable> {
+    blocks(stmt)
+        .iter()
+        .flat_map(|block| match block {
+            Block::Assignment(AssignmentBlock { var, expr, .. }) => {
+                let mut vars = expr.free_vars();
+                vars.insert(*var);
+                vars
+            }
+            Block::ArrayAssignment(ArrayAssignmentBlock {
+                array, index, expr, ..
+            }) => {
+                let mut vars: BTreeSet<Variable> = index
+                    .free_vars()
+                    .union(&expr.free_vars())
+                    .cloned()
+                    .collect();
+                vars.insert(*array);
+                vars
+            }
+            Block::Store(StoreBlock { ptr, expr, .. }) => {
+                let mut vars = expr.free_vars();
+                vars.insert(*ptr);
+                vars
+            }
+            Block::Skip(_) => BTreeSet::new(),
+            Block::Read(ReadBlock { var, .. }) => [*var].into(),
+            Block::Write(WriteBlock { expr, .. }) => expr.free_vars(),
+            Block::Test(TestBlock { expr, .. }) => expr.free_vars(),
+            Block::Entry(_) | Block::Exit(_) => BTreeSet::new(),
+            Block::Call(_) | Block::Return(_) => block
+                .bindings()
+                .into_iter()
+                .flat_map(|(var, expr)| expr.free_vars().into_iter().chain([var]))
+                .collect(),
+        })
+        .collect()
+}
+
+/// AExp*: the non-trivial arithmetic expressions occurring in a statement
+pub fn aexp_star(stmt: &Statement) -> BTreeSet<AExp> {
+    blocks(stmt)
+        .iter()
+        .flat_map(|block| match block {
+            Block::Assignment(AssignmentBlock { expr, .. }) => expr.subexpressions(),
+            Block::ArrayAssignment(ArrayAssignmentBlock { index, expr, .. }) => index
+                .subexpressions()
+                .union(&expr.subexpressions())
+                .cloned()
+                .collect(),
+            Block::Store(StoreBlock { expr, .. }) | Block::Write(WriteBlock { expr, .. }) => {
+                expr.subexpressions()
+            }
+            Block::Skip(_) | Block::Read(_) => BTreeSet::new(),
+            Block::Test(TestBlock { expr, .. }) => expr.subexpressions(),
+            Block::Entry(_) | Block::Exit(_) => BTreeSet::new(),
+            Block::Call(_) | Block::Return(_) => block
+                .bindings()
+                .iter()
+                .flat_map(|(_, expr)| expr.subexpressions())
+                .collect(),
+        })
+        .collect()
+}
@@ -0,0 +1,46 @@
+//! Data flow analysis of programs in the While language with procedures, following
+//! [_Principles of Program Analysis_](https://link.springer.com/book/10.1007/978-3-662-03811-6)
+//! by Flemming Nielson, Hanne Riis Nielson and Chris Hankin.
+//!
+//! Procedures are analyzed interprocedurally, either by the naive formulation of passing
+//! parameters as assignments, by the dedicated interprocedural live-variable analysis in
+//! [`analysis::ilv`], or context-sensitively using call strings with
+//! [`algorithm::call_strings`].
+//!
+//! A program is parsed with [`parser::parse`], which labels its blocks and returns a
+//! [`program::Program`]. Its control flow graph is given by the functions in [`functions`],
+//! which are also available as methods of the program. An analysis is an instance of the
+//! [`analysis::MonotoneFramework`] and is solved by one of the solvers in [`algorithm`],
+//! resulting in an [`analysis::Analysis`] with the entry and exit values at every label, which
+//! the renderers in [`output`] print as text, JSON, DOT or LaTeX.
+//!
+//! ```
+//! use lva::{algorithm::mfp, analysis::lv::LiveVariables, output::text, parser};
+//!
+//! let program = parser::parse("x := 2; y := 4; x := 1; z := x ").unwrap();
+//! let lva = mfp::run(&program, &LiveVariables::new(&program));
+//! print!("{}", text::render(&program, &lva));
+//! ```
+
+/// solvers computing the solution of an instance of the monotone framework
+pub mod algorithm;
+/// the monotone framework and its instances
+pub mod analysis;
+/// elementary blocks, the labelled building blocks of programs
+pub mod block;
+/// variables, arithmetic and boolean expressions
+pub mod expression;
+/// the control flow graph of statements: labels, blocks, flow and variables
+pub mod functions;
+/// presentation of programs and analysis results as text, JSON, DOT and LaTeX
+pub mod output;
+/// the parser for While programs
+pub mod parser;
+/// labelled programs
+pub mod program;
+/// solving the selected analyses of a program and presenting their results in one output
+pub mod report;
+/// the abstract syntax of statements
+pub mod statement;
+/// optimizing program transformations based on the analyses
+pub mod transform;
@@ -1,38 +1,240 @@
-mod algorithm;
-mod analysis;
-mod block;
-mod expression;
-mod functions;
-mod parser;
-mod program;
-mod statement;
-
+use clap::{Parser, ValueEnum};
+use lva::{
+    algorithm,
+    block::Block,
+    expression::Label,
+    output::{self, fmt_set, json, text},
+    parser,
+    program::Program,
+    report::{self, AnalysisKind, Options},
+    transform::Transformation,
+};
 use rustyline::{config::Configurer, DefaultEditor};
 use std::{
-    env,
+    collections::BTreeSet,
+    env, fs,
     io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
 };
 
+/// the analyses that can be selected with `--analysis`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum AnalysisName {
+    /// live variables
+    Lv,
+    /// interprocedural live variables
+    Ilv,
+    /// strongly live variables
+    Slv,
+    /// reaching definitions
+    Rd,
+    /// available expressions
+    Ae,
+    /// very busy expressions
+    Vb,
+    /// constant propagation
+    Cp,
+    /// intervals
+    Int,
+    /// detection of signs
+    Ds,
+    /// available copies
+    Ac,
+    /// use-definition and definition-use chains
+    Chains,
+    /// points-to sets
+    Pt,
+}
+
+/// the algorithms that can be selected with `--solver` to compute the solution of an analysis
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Solver {
+    /// maximal fixed point, using a worklist
+    Mfp,
+    /// chaotic iteration, updating all labels at once
+    Chaotic,
+    /// round-robin iteration, updating one label at a time
+    #[value(name = "roundrobin", alias = "round-robin")]
+    RoundRobin,
+}
+
+impl From<Solver> for algorithm::Solver {
+    fn from(solver: Solver) -> Self {
+        match solver {
+            Solver::Mfp => Self::Mfp,
+            Solver::Chaotic => Self::Chaotic,
+            Solver::RoundRobin => Self::RoundRobin,
+        }
+    }
+}
+
+impl From<AnalysisName> for AnalysisKind {
+    fn from(analysis: AnalysisName) -> Self {
+        match analysis {
+            AnalysisName::Lv => Self::Lv,
+            AnalysisName::Ilv => Self::Ilv,
+            AnalysisName::Slv => Self::Slv,
+            AnalysisName::Rd => Self::Rd,
+            AnalysisName::Ae => Self::Ae,
+            AnalysisName::Vb => Self::Vb,
+            AnalysisName::Cp => Self::Cp,
+            AnalysisName::Int => Self::Int,
+            AnalysisName::Ds => Self::Ds,
+            AnalysisName::Ac => Self::Ac,
+            AnalysisName::Chains => Self::Chains,
+            AnalysisName::Pt => Self::Pt,
+        }
+    }
+}
+
+/// the transformations that can be selected with `--transform`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Transform {
+    /// dead assignment elimination
+    Dae,
+    /// constant folding
+    Cf,
+    /// copy propagation
+    Copy,
+    /// common subexpression elimination
+    Cse,
+}
+
+impl From<Transform> for Transformation {
+    fn from(transform: Transform) -> Self {
+        match transform {
+            Transform::Dae => Self::Dae,
+            Transform::Cf => Self::Cf,
+            Transform::Copy => Self::Copy,
+            Transform::Cse => Self::Cse,
+        }
+    }
+}
+
+/// how programs and the results of analyzing them are printed
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Format {
+    /// one line per label
+    Text,
+    /// one document per program
+    Json,
+    /// the annotated control flow graph, for Graphviz
+    Dot,
+    /// a table with one row per block
+    Latex,
+}
+
+impl From<Format> for output::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Text => Self::Text,
+            Format::Json => Self::Json,
+            Format::Dot => Self::Dot,
+            Format::Latex => Self::Latex,
+        }
+    }
+}
+
+/// Data flow analysis of While programs
+#[derive(Clone, Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Files containing the programs to analyze; programs are read from standard input if
+    /// none are given
+    files: Vec<PathBuf>,
+
+    /// Analyses to perform, each of which is printed in turn
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "lv")]
+    analysis: Vec<AnalysisName>,
+
+    /// The algorithm used to solve the analysis
+    #[arg(short, long, value_enum, default_value_t = Solver::Mfp)]
+    solver: Solver,
+
+    /// Transformations to apply, in order, before analyzing the program
+    #[arg(short, long, value_enum, value_delimiter = ',')]
+    transform: Vec<Transform>,
+
+    /// How to print the program and the results
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Also solve the analysis context-sensitively, distinguishing the calls of procedures by
+    /// their K most recent calls, and print the results for each call string
+    #[arg(short = 'k', long, value_name = "K")]
+    call_string_depth: Option<usize>,
+}
+
+/// the name a value is selected by on the command line
+fn name(value: impl ValueEnum) -> String {
+    value.to_possible_value().unwrap().get_name().to_string()
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+
+    // files given as arguments -> analyze them and exit, failing if any of them does not parse
+    if !cli.files.is_empty() {
+        let mut success = true;
+        for file in &cli.files {
+            match load(&cli, file) {
+                Some(program) => analyze(&cli, program),
+                None => success = false,
+            }
+        }
+        process::exit(if success { 0 } else { 1 });
+    }
+
+    // piped input -> analyze it as a single program and exit
+    if !io::stdin().is_terminal() {
+        let input = io::read_to_string(io::stdin()).unwrap_or_else(|err| {
+            eprintln!("Error reading standard input: {err}");
+            process::exit(1);
+        });
+        if input.trim().is_empty() {
+            return;
+        }
+        match parse(&cli, None, &input) {
+            Some(program) => analyze(&cli, program),
+            None => process::exit(1),
+        }
+        return;
+    }
+
+    repl(cli);
+}
 
-    let is_terminal = io::stdin().is_terminal();
+/// reads programs and meta-commands from the terminal until a blank program is entered
+fn repl(cli: Cli) {
+    let bin = env::args().next().unwrap_or("lva".to_string());
     let mut rl = DefaultEditor::new().unwrap();
     rl.set_auto_add_history(true);
 
-    if is_terminal {
-        println!("Enter statements here! Examples can be found in ./example_program.");
-        println!("To finish the program, press Ctrl+D or submit a blank line.");
-        println!("To use an input file, run: {} < (path/to/file)", args[0]);
-        println!("To exit, press Ctrl+C or submit a blank program.")
-    }
+    println!("Enter statements here! Examples can be found in ./example_program.");
+    println!("To finish the program, press Ctrl+D or submit a blank line.");
+    println!(
+        "To inspect the last program or change the analysis, enter :help for a list of commands."
+    );
+    println!("To analyze files instead, run: {bin} (path/to/file)...");
+    println!("To select an analysis, solver, transformations or output format, see: {bin} --help");
+    println!("To exit, press Ctrl+C or submit a blank program.");
+
+    let mut cli = cli;
+    let mut current: Option<Program> = None;
 
     loop {
         let mut rl_prompt = ">>> ";
 
         let mut input = String::new();
         while let Ok(line) = rl.readline(rl_prompt) {
-            if line.len() == 0 && is_terminal {
+            if line.is_empty() {
+                break;
+            }
+
+            // meta-commands are only recognized in place of a new program
+            if input.is_empty() && line.trim_start().starts_with(':') {
+                input.push_str(&line);
                 break;
             }
 
@@ -41,46 +243,181 @@ fn main() {
             rl_prompt = "... ";
         }
 
-        input = input.trim_end().to_string();
-        if input.len() == 0 {
+        if input.trim().is_empty() {
             return;
         }
-        input.push(' ');
-        let program = parser::parse(&input);
 
-        // parse error -> print location of the error
-        if let Err(err) = program {
-            println!(
-                "\nError parsing program at line {}, column {}:",
-                err.location.line, err.location.column
-            );
+        if let Some(command) = input.trim().strip_prefix(':') {
+            meta_command(&mut cli, &mut current, command);
+            continue;
+        }
 
-            let line = input.split('\n').take(err.location.line).last().unwrap();
-            println!("{line}");
-            println!("{:>col$}", "^", col = err.location.column);
+        if let Some(program) = parse(&cli, None, &input) {
+            analyze(&cli, program.clone());
+            current = Some(program);
+        }
+    }
+}
 
-            println!("Expected {}", err.expected);
+/// executes a REPL meta-command (without the leading `:`), which either inspects the last
+/// parsed program or changes how programs are analyzed
+fn meta_command(cli: &mut Cli, current: &mut Option<Program>, command: &str) {
+    let (command, arg) = match command.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (command, ""),
+    };
 
-            continue;
+    match command {
+        "help" => {
+            println!(":flow             the flow of the last program");
+            println!(":flowR            the reverse flow of the last program");
+            println!(":blocks           the blocks of the last program");
+            println!(":init             the initial label of the last program");
+            println!(":final            the final labels of the last program");
+            println!(":labels           the labels of the last program");
+            println!(":analysis <names> select comma-separated analyses and apply them to the last program");
+            println!(":solver <name>    select a solver and apply it to the last program");
+            println!(":load <file>      parse and analyze a program from a file");
+            return;
+        }
+
+        "load" => {
+            if let Some(program) = load(cli, Path::new(arg)) {
+                analyze(cli, program.clone());
+                *current = Some(program);
+            }
+            return;
         }
 
-        // parsed successfully -> proceed with analysis
-        let program = program.unwrap();
+        "analysis" => match arg
+            .split(',')
+            .map(|name| select(name.trim(), "analysis"))
+            .collect()
+        {
+            Some(analyses) => cli.analysis = analyses,
+            None => return,
+        },
 
-        println!("Program: {}", program);
-        println!("Flow: {:?}", program.flow_r());
-        println!();
+        "solver" => match select(arg, "solver") {
+            Some(solver) => cli.solver = solver,
+            None => return,
+        },
+
+        "flow" | "flowR" | "blocks" | "init" | "final" | "labels" => {}
 
-        // let lva = algorithm::chaotic_iter::run(&program);
-        let lva = algorithm::mfp::run(&program);
+        _ => {
+            eprintln!("Unknown command ':{command}', enter :help for a list of commands");
+            return;
+        }
+    }
 
-        for label in 1..=program.len {
-            println!(
-                "{label}: entry={:?}, exit={:?}",
-                lva.entry[&label], lva.exit[&label],
-            )
+    let Some(program) = current else {
+        if !matches!(command, "analysis" | "solver") {
+            eprintln!("No program has been entered yet");
         }
+        return;
+    };
 
-        println!();
+    let fmt_edge = |(l, l_p): &(Label, Label)| format!("({l}, {l_p})");
+    match command {
+        "flow" => println!("{}", fmt_set(&program.flow(), fmt_edge)),
+        "flowR" => println!("{}", fmt_set(&program.flow_r(), fmt_edge)),
+        "blocks" => {
+            let mut blocks: Vec<Block> = program.blocks().into_iter().collect();
+            blocks.sort_by_key(Block::get_label);
+            for block in blocks {
+                println!("{block}");
+            }
+        }
+        "init" => println!("{}", program.init_label()),
+        "final" => println!("{}", fmt_set(&program.final_labels(), |l| *l)),
+        "labels" => {
+            let labels: BTreeSet<Label> = (1..=program.len).collect();
+            println!("{}", fmt_set(&labels, |l| *l));
+        }
+        _ => analyze(cli, program.clone()),
     }
 }
+
+/// looks up the value of a command-line option by the name it is selected by, printing the
+/// possible values if there is none with that name
+fn select<T: ValueEnum>(name: &str, option: &str) -> Option<T> {
+    T::from_str(name, false)
+        .map_err(|_| {
+            let names: Vec<String> = T::value_variants()
+                .iter()
+                .cloned()
+                .map(self::name)
+                .collect();
+            eprintln!(
+                "Unknown {option} '{name}', expected one of: {}",
+                names.join(", ")
+            );
+        })
+        .ok()
+}
+
+/// reads and parses a program from a file
+fn load(cli: &Cli, file: &Path) -> Option<Program> {
+    match fs::read_to_string(file) {
+        Ok(input) => parse(cli, Some(file), &input),
+        Err(err) => {
+            eprintln!("Error reading {}: {err}", file.display());
+            None
+        }
+    }
+}
+
+/// parses a program, printing the location of the error if it cannot be parsed
+fn parse(cli: &Cli, file: Option<&Path>, input: &str) -> Option<Program> {
+    let mut input = input.trim_end().to_string();
+    input.push(' ');
+
+    let err = match parser::parse(&input) {
+        Ok(program) => return Some(program),
+        Err(err) => err,
+    };
+
+    let file = file.map(|file| file.display().to_string());
+    if cli.format == Format::Json {
+        println!("{}", json::parse_error(&err, file.as_deref()));
+    } else {
+        let source = file.unwrap_or("program".to_string());
+        eprint!("{}", text::render_parse_error(&source, &input, &err));
+    }
+
+    None
+}
+
+/// applies the selected transformations to a program, then prints it together with the
+/// results of the selected analyses
+fn analyze(cli: &Cli, program: Program) {
+    let mut program = program;
+
+    if cli.format == Format::Text {
+        println!("Program: {}", program);
+    }
+    for transform in &cli.transform {
+        match Transformation::from(*transform).run(&program) {
+            Ok(transformed) => program = transformed,
+            Err(err) => {
+                eprintln!("Skipping {}: {err}", name(*transform));
+                continue;
+            }
+        }
+        if cli.format == Format::Text {
+            println!("After {}: {}", name(*transform), program);
+        }
+    }
+    if cli.format == Format::Text {
+        println!("{}", text::render_flow(&program));
+    }
+
+    let options = Options {
+        solver: cli.solver.into(),
+        call_string_depth: cli.call_string_depth,
+        format: cli.format.into(),
+    };
+    let analyses: Vec<AnalysisKind> = cli.analysis.iter().map(|a| (*a).into()).collect();
+    print!("{}", report::render(&program, &analyses, &options));
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    analysis::{
+        ac::CopyAssignment,
+        cp::ConstValue,
+        ds::Signs,
+        interval::{Bound, Interval},
+        rd::Definition,
+    },
+    expression::{AExp, Variable},
+};
+
+pub mod dot;
+pub mod json;
+pub mod latex;
+pub mod text;
+
+/// how programs and the results of analyzing them are presented
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// one line per label
+    Text,
+    /// one document per program
+    Json,
+    /// the annotated control flow graph, for Graphviz
+    Dot,
+    /// a table with one row per block
+    Latex,
+}
+
+/// how the values of an analysis are presented to humans and machines
+pub trait Present {
+    /// formats the value as text, e.g. `{x, y}`
+    fn to_text(&self) -> String;
+
+    /// converts the value to JSON, e.g. `["x", "y"]`
+    fn to_json(&self) -> Value;
+}
+
+/// formats a set as `{a, b, c}`, using `fmt` for each of its elements
+pub fn fmt_set<T, D: Display>(set: &BTreeSet<T>, fmt: impl Fn(&T) -> D) -> String {
+    let items: Vec<String> = set.iter().map(|item| fmt(item).to_string()).collect();
+    format!("{{{}}}", items.join(", "))
+}
+
+/// formats a definition as `(x, l)`, or `(x, ?)` if it is not defined in the program
+pub fn fmt_definition((var, label): &Definition) -> String {
+    match label {
+        Some(label) => format!("({var}, {label})"),
+        None => format!("({var}, ?)"),
+    }
+}
+
+impl Present for BTreeSet<Variable> {
+    fn to_text(&self) -> String {
+        fmt_set(self, |var| *var)
+    }
+
+    fn to_json(&self) -> Value {
+        self.iter().map(|var| var.name()).collect()
+    }
+}
+
+impl Present for BTreeSet<Definition> {
+    fn to_text(&self) -> String {
+        fmt_set(self, fmt_definition)
+    }
+
+    /// `[x, l]`, or `[x, null]` if x is not defined in the program
+    fn to_json(&self) -> Value {
+        self.iter()
+            .map(|(var, label)| json!([var.name(), label]))
+            .collect()
+    }
+}
+
+impl Present for BTreeSet<AExp> {
+    fn to_text(&self) -> String {
+        fmt_set(self, |expr| expr.clone())
+    }
+
+    fn to_json(&self) -> Value {
+        self.iter().map(|expr| expr.to_string()).collect()
+    }
+}
+
+impl Present for BTreeSet<CopyAssignment> {
+    fn to_text(&self) -> String {
+        fmt_set(self, |(x, y)| format!("{x} := {y}"))
+    }
+
+    /// `[x, y]` for the copy x := y
+    fn to_json(&self) -> Value {
+        self.iter()
+            .map(|(x, y)| json!([x.name(), y.name()]))
+            .collect()
+    }
+}
+
+/// abstract states mapping each variable to an abstract value
+impl<V: Present> Present for BTreeMap<Variable, V> {
+    fn to_text(&self) -> String {
+        let items: Vec<String> = self
+            .iter()
+            .map(|(var, value)| format!("{var} ↦ {}", value.to_text()))
+            .collect();
+        format!("{{{}}}", items.join(", "))
+    }
+
+    fn to_json(&self) -> Value {
+        self.iter()
+            .map(|(var, value)| (var.name().to_string(), value.to_json()))
+            .collect::<serde_json::Map<String, Value>>()
+            .into()
+    }
+}
+
+impl Present for ConstValue {
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// the constant as a number, otherwise `"⊥"` or `"⊤"`
+    fn to_json(&self) -> Value {
+        match self {
+            ConstValue::Const(val) => json!(val),
+            _ => json!(self.to_string()),
+        }
+    }
+}
+
+impl Present for Interval {
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// `[lo, hi]` with infinite bounds as `"-∞"` and `"∞"`, or `null` if empty
+    fn to_json(&self) -> Value {
+        let bound = |bound: &Bound| match bound {
+            Bound::Finite(val) => json!(val),
+            _ => json!(bound.to_string()),
+        };
+
+        match self {
+            Interval::Empty => Value::Null,
+            Interval::Range(lo, hi) => json!([bound(lo), bound(hi)]),
+        }
+    }
+}
+
+impl Present for Signs {
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    /// the signs as an array of `"-"`, `"0"` and `"+"`
+    fn to_json(&self) -> Value {
+        self.iter().map(|sign| sign.to_string()).collect()
+    }
+}
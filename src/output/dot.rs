@@ -0,0 +1,80 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    analysis::Analysis, block::Block, expression::Label, output::Present, program::Program,
+};
+
+/// the formatted entry and exit values of an analysis at each label
+pub type Annotations = BTreeMap<Label, (String, String)>;
+
+/// formats the entry and exit values of an analysis as annotations for `render`; labels that
+/// are never reached are left unannotated
+pub fn annotations<L: Present>(program: &Program, analysis: &Analysis<L>) -> Annotations {
+    (1..=program.len)
+        .filter(|label| !analysis.unreachable.contains(label))
+        .map(|label| {
+            let (entry, exit) = (&analysis.entry[&label], &analysis.exit[&label]);
+            (label, (entry.to_text(), exit.to_text()))
+        })
+        .collect()
+}
+
+/// renders the control flow graph of a program in the DOT language of Graphviz, optionally
+/// annotating each block with its entry and exit values
+pub fn render(program: &Program, annotations: Option<&Annotations>) -> String {
+    let mut dot = String::from("digraph cfg {\n    node [shape=box];\n\n");
+
+    let mut blocks: Vec<Block> = program.blocks().into_iter().collect();
+    blocks.sort_by_key(Block::get_label);
+    for block in blocks {
+        let label = block.get_label();
+        let text = match annotations.and_then(|annotations| annotations.get(&label)) {
+            Some((entry, exit)) => format!("entry: {entry}\n{block}\nexit: {exit}"),
+            None => block.to_string(),
+        };
+        let style = match block {
+            Block::Test(_) => ", style=rounded",
+            _ => "",
+        };
+
+        writeln!(dot, "    {label} [label=\"{}\"{style}];", escape(&text)).unwrap();
+    }
+    dot.push('\n');
+
+    // edges out of if/while tests are marked with the outcome they are taken for, edges into
+    // and out of procedures are dashed
+    let true_flow = program.true_flow();
+    let branches: BTreeSet<Label> = true_flow.iter().map(|(l, _)| *l).collect();
+    let inter_flow: BTreeSet<(Label, Label)> = program
+        .inter_flow()
+        .into_iter()
+        .flat_map(|(l_c, l_n, l_x, l_r)| [(l_c, l_n), (l_x, l_r)])
+        .collect();
+
+    for (l, l_p) in program.flow() {
+        let attributes = if true_flow.contains(&(l, l_p)) {
+            " [label=\"true\"]"
+        } else if branches.contains(&l) {
+            " [label=\"false\"]"
+        } else if inter_flow.contains(&(l, l_p)) {
+            " [style=dashed]"
+        } else {
+            ""
+        };
+
+        writeln!(dot, "    {l} -> {l_p}{attributes};").unwrap();
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// escapes a string for use inside a quoted DOT identifier
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,147 @@
+use peg::{error::ParseError, str::LineCol};
+use serde_json::{json, Value};
+
+use crate::{
+    analysis::{
+        chains::{du_by_label, ud_by_label, DUChains, UDChains},
+        pt::{Location, PointsTo},
+        Analysis, ContextAnalysis,
+    },
+    block::{Block, TestBlock},
+    expression::Label,
+    output::Present,
+    program::Program,
+};
+
+/// describes a program by its text, labels, blocks, flow and inter-flow
+pub fn program(program: &Program) -> Value {
+    let mut blocks: Vec<Block> = program.blocks().into_iter().collect();
+    blocks.sort_by_key(Block::get_label);
+
+    json!({
+        "text": program.to_string(),
+        "labels": (1..=program.len).collect::<Vec<Label>>(),
+        "init": program.init_label(),
+        "final": program.final_labels(),
+        "blocks": blocks
+            .iter()
+            .map(|block| {
+                let kind = match block {
+                    Block::Assignment(_) => "assignment",
+                    Block::ArrayAssignment(_) => "array_assignment",
+                    Block::Store(_) => "store",
+                    Block::Skip(_) => "skip",
+                    Block::Read(_) => "read",
+                    Block::Write(_) => "write",
+                    Block::Test(_) => "test",
+                    Block::Entry(_) => "entry",
+                    Block::Exit(_) => "exit",
+                    Block::Call(_) => "call",
+                    Block::Return(_) => "return",
+                };
+                json!({ "label": block.get_label(), "kind": kind, "text": block.to_string() })
+            })
+            .collect::<Vec<Value>>(),
+        "flow": program.flow(),
+        "inter_flow": program.inter_flow(),
+    })
+}
+
+/// the entry and exit values of an analysis at every label of the program that is reached
+pub fn analysis<L: Present>(program: &Program, analysis: &Analysis<L>) -> Value {
+    (1..=program.len)
+        .filter(|label| !analysis.unreachable.contains(label))
+        .map(|label| {
+            json!({
+                "label": label,
+                "entry": analysis.entry[&label].to_json(),
+                "exit": analysis.exit[&label].to_json(),
+            })
+        })
+        .collect()
+}
+
+/// the entry and exit values of a context-sensitive analysis for every label of the program
+/// and call string it is reached with
+pub fn contexts<L: Present>(program: &Program, analysis: &ContextAnalysis<L>) -> Value {
+    let results: Vec<Value> = (1..=program.len)
+        .flat_map(|label| {
+            let (entry, exit) = (&analysis.entry[&label], &analysis.exit[&label]);
+            entry.keys().map(move |context| {
+                json!({
+                    "label": label,
+                    "call_string": context,
+                    "entry": entry[context].to_json(),
+                    "exit": exit[context].to_json(),
+                })
+            })
+        })
+        .collect();
+
+    json!({ "depth": analysis.depth, "results": results })
+}
+
+/// the label and outcome of every test whose outcome is already known
+pub fn constant_tests(tests: &[(TestBlock, bool)]) -> Value {
+    tests
+        .iter()
+        .map(|(test, value)| json!({ "label": test.label, "value": value }))
+        .collect()
+}
+
+/// ud(x, l) for every use and du(x, l) for every definition
+pub fn chains(ud: &UDChains, du: &DUChains) -> Value {
+    json!({
+        "ud": ud_by_label(ud)
+            .into_iter()
+            .map(|((var, label), defs)| json!({ "var": var.name(), "label": label, "defs": defs }))
+            .collect::<Vec<_>>(),
+        "du": du_by_label(du)
+            .into_iter()
+            .map(|((var, label), uses)| json!({ "var": var.name(), "label": label, "uses": uses }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// pt(l) for every location that may point somewhere, with variables given by their names
+/// and heap cells by the labels they are allocated at
+pub fn points_to(pt: &PointsTo) -> Value {
+    let location = |location: &Location| match location {
+        Location::Variable(var) => json!(var.name()),
+        Location::Heap(label) => json!({ "new": label }),
+    };
+
+    pt.sets()
+        .iter()
+        .map(|(l, targets)| {
+            json!({
+                "location": location(l),
+                "targets": targets.iter().map(location).collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+/// a complete document describing a program and the results of the named analyses on it
+pub fn document(program: &Program, analyses: Vec<(&str, Value)>) -> Value {
+    json!({
+        "program": self::program(program),
+        "analyses": analyses
+            .into_iter()
+            .map(|(name, results)| (name.to_string(), results))
+            .collect::<serde_json::Map<String, Value>>(),
+    })
+}
+
+/// describes why a program, optionally read from `file`, cannot be parsed
+pub fn parse_error(err: &ParseError<LineCol>, file: Option<&str>) -> Value {
+    let mut error = json!({
+        "line": err.location.line,
+        "column": err.location.column,
+        "expected": err.expected.to_string(),
+    });
+    if let Some(file) = file {
+        error["file"] = json!(file);
+    }
+    json!({ "error": error })
+}
@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+use crate::{analysis::Analysis, output::Present, program::Program};
+
+/// textual operators and symbols and the math mode commands they are replaced with, in the
+/// order the replacements are applied
+const REPLACEMENTS: [(&str, &str); 14] = [
+    ("{", "\\{"),
+    ("}", "\\}"),
+    ("_", "\\_"),
+    ("&&", "\\land "),
+    ("&", "\\&"),
+    ("||", "\\lor "),
+    ("!=", "\\neq "),
+    ("<=", "\\leq "),
+    (">=", "\\geq "),
+    ("!", "\\neg "),
+    ("↦", "\\mapsto"),
+    ("⊤", "\\top"),
+    ("⊥", "\\bot"),
+    ("∞", "\\infty"),
+];
+
+/// converts text produced by `Present::to_text` or the `Display` implementations to math mode
+/// source
+pub fn escape(text: &str) -> String {
+    REPLACEMENTS
+        .iter()
+        .fold(text.to_string(), |text, (from, to)| text.replace(from, to))
+}
+
+/// typesets text in inline math mode
+pub fn math(text: &str) -> String {
+    format!("${}$", escape(text))
+}
+
+/// renders the entry and exit values of an analysis at every label of the program as a LaTeX
+/// table, with one row per block
+pub fn render<L: Present>(program: &Program, name: &str, analysis: &Analysis<L>) -> String {
+    let name = name.to_uppercase();
+    let mut latex = String::from("\\begin{tabular}{r|l|l|l}\n");
+    writeln!(
+        latex,
+        "$\\ell$ & $B^\\ell$ & $\\mathit{{{name}}}_\\circ(\\ell)$ & $\\mathit{{{name}}}_\\bullet(\\ell)$ \\\\"
+    )
+    .unwrap();
+    latex.push_str("\\hline\n");
+
+    for label in 1..=program.len {
+        // the label is typeset as a proper superscript instead of unicode superscript (and, for
+        // calls, subscript) digits
+        let block = program.at(label).unwrap().to_string();
+        let block = block.trim_end_matches(|c| "⁰¹²³⁴⁵⁶⁷⁸⁹₀₁₂₃₄₅₆₇₈₉".contains(c));
+
+        let (entry, exit) = if analysis.unreachable.contains(&label) {
+            let unreachable = "\\multicolumn{2}{l}{unreachable}".to_string();
+            (unreachable, String::new())
+        } else {
+            (
+                format!("{} & ", math(&analysis.entry[&label].to_text())),
+                math(&analysis.exit[&label].to_text()),
+            )
+        };
+
+        writeln!(
+            latex,
+            "{label} & ${}^{{{label}}}$ & {entry}{exit} \\\\",
+            escape(block),
+        )
+        .unwrap();
+    }
+
+    latex.push_str("\\end{tabular}\n");
+    latex
+}
+
+/// typesets text with one formula per line, as produced by e.g. `text::render_chains`, as lines
+/// of inline math
+pub fn render_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("{} \\\\\n", math(line)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm, analysis::lv::LiveVariables, parser};
+
+    #[test]
+    fn address_of_is_escaped() {
+        let program = parser::parse("x := &y; *x := 1 ").unwrap();
+        let lv = algorithm::mfp::run(&program, &LiveVariables::new(&program));
+        let latex = render(&program, "lv", &lv);
+
+        assert!(latex.contains("1 & $[x := \\&y]^{1}$ & "));
+        assert!(escape("a && b").contains("\\land"));
+    }
+}
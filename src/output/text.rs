@@ -0,0 +1,117 @@
+use peg::{error::ParseError, str::LineCol};
+
+use crate::{
+    analysis::{
+        chains::{du_by_label, ud_by_label, DUChains, UDChains},
+        pt::PointsTo,
+        Analysis, CallString, ContextAnalysis,
+    },
+    block::{Block, TestBlock},
+    output::{fmt_set, Present},
+    program::Program,
+};
+
+/// the entry and exit values of an analysis at every label of the program, one label per line
+pub fn render<L: Present>(program: &Program, analysis: &Analysis<L>) -> String {
+    (1..=program.len)
+        .map(|label| {
+            if analysis.unreachable.contains(&label) {
+                return format!("{label}: unreachable\n");
+            }
+
+            format!(
+                "{label}: entry={}, exit={}\n",
+                analysis.entry[&label].to_text(),
+                analysis.exit[&label].to_text(),
+            )
+        })
+        .collect()
+}
+
+/// formats a call string as `[l1, l2, ...]`, most recent call first
+pub fn fmt_call_string(context: &CallString) -> String {
+    let calls: Vec<String> = context.iter().map(|label| label.to_string()).collect();
+    format!("[{}]", calls.join(", "))
+}
+
+/// the entry and exit values of a context-sensitive analysis at every label of the program,
+/// one line per call string the label is reached with
+pub fn render_contexts<L: Present>(program: &Program, analysis: &ContextAnalysis<L>) -> String {
+    (1..=program.len)
+        .map(|label| {
+            let (entry, exit) = (&analysis.entry[&label], &analysis.exit[&label]);
+            if entry.is_empty() {
+                return format!("{label}: unreachable\n");
+            }
+
+            entry
+                .keys()
+                .map(|context| {
+                    format!(
+                        "{label} {}: entry={}, exit={}\n",
+                        fmt_call_string(context),
+                        entry[context].to_text(),
+                        exit[context].to_text(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// the tests whose outcome is already known, one per line
+pub fn render_constant_tests(tests: &[(TestBlock, bool)]) -> String {
+    tests
+        .iter()
+        .map(|(test, value)| format!("{} is always {value}\n", Block::Test(test.clone())))
+        .collect()
+}
+
+/// ud(x, l) for every use and du(x, l) for every definition, one per line
+pub fn render_chains(ud: &UDChains, du: &DUChains) -> String {
+    let ud_lines = ud_by_label(ud).into_iter().map(|((var, label), defs)| {
+        let defs = fmt_set(defs, |def| match def {
+            Some(def) => def.to_string(),
+            None => "?".to_string(),
+        });
+        format!("ud({var}, {label}) = {defs}\n")
+    });
+    let du_lines = du_by_label(du).into_iter().map(|((var, label), uses)| {
+        let label = label.map_or("?".to_string(), |label| label.to_string());
+        format!("du({var}, {label}) = {}\n", fmt_set(uses, |l| *l))
+    });
+
+    ud_lines.chain(du_lines).collect()
+}
+
+/// pt(l) for every location that may point somewhere, one per line
+pub fn render_points_to(pt: &PointsTo) -> String {
+    pt.sets()
+        .iter()
+        .map(|(location, targets)| format!("pt({location}) = {}\n", fmt_set(targets, |l| *l)))
+        .collect()
+}
+
+/// the flow of a program, as printed before the results of analyzing it, followed by its
+/// inter-flow if it declares procedures
+pub fn render_flow(program: &Program) -> String {
+    let mut text = format!("Flow: {:?}\n", program.flow_r());
+    if !program.procedures.is_empty() {
+        text.push_str(&format!("Inter-flow: {:?}\n", program.inter_flow()));
+    }
+    text
+}
+
+/// describes why a program from `source` cannot be parsed, pointing at the location of the
+/// error in its line of `input`
+pub fn render_parse_error(source: &str, input: &str, err: &ParseError<LineCol>) -> String {
+    let line = input.split('\n').take(err.location.line).last().unwrap();
+    format!(
+        "\nError parsing {source} at line {}, column {}:\n{line}\n{:>col$}\nExpected {}\n",
+        err.location.line,
+        err.location.column,
+        "^",
+        err.expected,
+        col = err.location.column,
+    )
+}
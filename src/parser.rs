@@ -1,10 +1,10 @@
 use peg::{self, error::ParseError, str::LineCol};
 
 use crate::{
-    block::{Block, TestBlock},
+    block::{Block, CallBlock, TestBlock},
     expression::{AExp, BExp, Value, Variable},
     program::Program,
-    statement::Statement,
+    statement::{Procedure, Statement},
 };
 
 peg::parser!(grammar while_() for str {
@@ -14,16 +14,26 @@ peg::parser!(grammar while_() for str {
     rule alpha() -> char = quiet!{ ['a'..='z' | 'A'..='Z'] }
     rule digit() -> char = quiet!{ ['0'..='9'] }
     rule neg() -> char = quiet!{ ['-'] }
-    rule keyword() = quiet! { "if" / "then" / "else" / "endif" / "while" / "do" / "enddo" }
+    rule ident_char() -> char = quiet!{ alpha() / digit() / ['_'] }
+    rule keyword() = quiet!{
+        ("if" / "then" / "else" / "endif" / "while" / "do" / "enddo" / "skip" / "true" / "false"
+            / "begin" / "end" / "proc" / "is" / "val" / "res" / "call" / "new"
+            / "read" / "write")
+        !ident_char()
+    }
 
     rule constant() -> Value
         = n:$(neg()? digit()+) {? n.parse().or(Err("i32")) }
         / expected!("constant")
 
     rule variable() -> Variable
-        = !keyword() x:alpha() ws_or_eof() { x }
+        = !keyword() x:$(alpha() ident_char()*) ws_or_eof() { Variable::new(x) }
         / expected!("variable")
 
+    rule name() -> String
+        = !keyword() p:$(alpha() ident_char()*) ws_or_eof() { p.to_string() }
+        / expected!("procedure name")
+
     rule aexp() -> AExp
         = t:precedence!{
             x:(@) _ op:$("+" / "-") _ y:@ { AExp::ArithmeticOp(Box::new(x), op.to_string(), Box::new(y)) }
@@ -31,11 +41,19 @@ peg::parser!(grammar while_() for str {
             x:(@) _ op:$("*" / "/") _ y:@ { AExp::ArithmeticOp(Box::new(x), op.to_string(), Box::new(y)) }
             --
             n:constant() { AExp::Number(n) }
+            a:variable() _ "[" _ i:aexp() _ "]" { AExp::ArrayElement(a, Box::new(i)) }
+            "*" _ p:variable() { AExp::Deref(p) }
             v:variable() { AExp::Variable(v) }
             --
             "(" _ t:aexp() _ ")" { t }
         }
 
+    // addresses can only be assigned as a whole
+    rule rhs() -> AExp
+        = "&" _ x:variable() { AExp::AddressOf(x) }
+        / "new" !ident_char() { AExp::New }
+        / aexp()
+
     rule bexp() -> BExp
         = t:precedence!{
             x:(@) _ op:$("||") _ y:@ { BExp::BooleanOp(Box::new(x), op.to_string(), Box::new(y)) }
@@ -61,10 +79,19 @@ peg::parser!(grammar while_() for str {
             Statement::While(TestBlock { label: 0, expr: t0 }, Box::new(t1))
         }
 
+    rule call() -> Statement
+        = "call" __ p:name() _ "(" _ args:(aexp() ** (_ "," _)) _ ")" {
+            Statement::Call(CallBlock::new(p, args))
+        }
+
     rule atom() -> Statement
         = b:(
-            x:variable() _ ":=" _ e:aexp() { Block::assignment(0, x, e) }
+            a:variable() _ "[" _ i:aexp() _ "]" _ ":=" _ e:aexp() { Block::array_assignment(0, a, i, e) }
+            / "*" _ p:variable() _ ":=" _ e:rhs() { Block::store(0, p, e) }
+            / x:variable() _ ":=" _ e:rhs() { Block::assignment(0, x, e) }
             / "skip" { Block::skip(0) }
+            / "read" !ident_char() _ x:variable() { Block::read(0, x) }
+            / "write" !ident_char() _ e:aexp() { Block::write(0, e) }
             / e:bexp() { Block::test(0, e) }
         ) { Statement::Atom(b) }
 
@@ -76,12 +103,59 @@ peg::parser!(grammar while_() for str {
             --
             s:while() { s }
             --
+            s:call() { s }
             s:atom() { s }
         } { s }
 
-        pub rule program() -> Program = s:stmt() _ { Program::new(s) }
+    rule params() -> (Vec<Variable>, Vec<Variable>)
+        = "val" __ val:(variable() ++ (_ "," _)) res:(_ "," _ "res" __ res:(variable() ++ (_ "," _)) { res })? {
+            (val, res.unwrap_or_default())
+        }
+        / "res" __ res:(variable() ++ (_ "," _)) { (vec![], res) }
+        / { (vec![], vec![]) }
+
+    rule procedure() -> Procedure
+        = "proc" __ p:name() _ "(" _ ps:params() _ ")" __ "is" __ s:stmt() __ "end" !ident_char() {
+            Procedure::new(p, ps.0, ps.1, s)
+        }
+
+    rule declarations() -> Vec<Procedure>
+        = ds:(_ d:procedure() _ ";"? { d })* { ds }
+
+        pub rule program() -> Program
+            = _ "begin" __ ds:declarations() s:stmt() __ "end" _ {? Program::with_procedures(ds, s) }
+            / ds:declarations() s:stmt() _ {? Program::with_procedures(ds, s) }
 });
 
+/// parses a While program and labels its blocks in order, starting from 1
 pub fn parse(input: &str) -> Result<Program, ParseError<LineCol>> {
     while_::program(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the variable assigned by a program consisting of a single assignment
+    fn assigned(input: &str) -> Option<Variable> {
+        match parse(input).ok()?.at(1)? {
+            Block::Assignment(assignment) => Some(assignment.var),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn identifiers_may_start_with_keywords() {
+        assert_eq!(assigned("ifx := 1 "), Some(Variable::new("ifx")));
+        assert_eq!(assigned("do_x := 2 "), Some(Variable::new("do_x")));
+        assert_eq!(assigned("skipped := 3 "), Some(Variable::new("skipped")));
+        assert_eq!(assigned("x1 := 4 "), Some(Variable::new("x1")));
+    }
+
+    #[test]
+    fn keywords_are_not_identifiers() {
+        assert!(parse("if := 1 ").is_err());
+        assert!(parse("x := skip ").is_err());
+        assert!(parse("1x := 1 ").is_err());
+    }
+}
@@ -1,49 +1,215 @@
-use std::{collections::HashSet, fmt::Display};
+use std::{collections::BTreeSet, fmt::Display};
 
 use crate::{
-    block::{AssignmentBlock, Block, SkipBlock, TestBlock},
-    expression::Label,
+    block::{
+        ArrayAssignmentBlock, AssignmentBlock, Block, CallBlock, ReadBlock, SkipBlock, StoreBlock,
+        TestBlock, WriteBlock,
+    },
+    expression::{AExp, Label, Variable},
     functions,
-    statement::Statement,
+    statement::{Procedure, Statement},
 };
 
-/// encapsulates a sequence of `Statement`s starting at `1`
+/// encapsulates the procedure declarations and the main statement of a program, labelled
+/// sequentially starting at `1`, the procedures first
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Program {
+    pub procedures: Vec<Procedure>,
     pub contents: Statement,
     pub len: usize,
 }
 
 #[allow(dead_code)]
 impl Program {
-    /// creates a new program, labelling all its statements sequentially
+    /// creates a new program without procedures, labelling all its statements sequentially;
+    /// panics if it contains calls
     pub fn new(contents: Statement) -> Self {
-        let (contents, next) = Program::relabel(contents, 1);
-        Self {
+        Self::with_procedures(vec![], contents).unwrap()
+    }
+
+    /// creates a new program, labelling the procedures and then the main statement
+    /// sequentially, and resolves the procedures called by its calls; fails if a procedure is
+    /// declared twice or a call does not match a declaration
+    pub fn with_procedures(
+        procedures: Vec<Procedure>,
+        contents: Statement,
+    ) -> Result<Self, &'static str> {
+        let mut next = 1;
+        let mut labelled = vec![];
+        for proc in procedures {
+            if labelled.iter().any(|p: &Procedure| p.name == proc.name) {
+                return Err("distinct procedure names");
+            }
+
+            let (body, exit) = Program::relabel(proc.body, next + 1);
+            labelled.push(Procedure {
+                entry: next,
+                exit,
+                body,
+                ..proc
+            });
+            next = exit + 1;
+        }
+        let (contents, next) = Program::relabel(contents, next);
+
+        let procedures = labelled
+            .iter()
+            .map(|proc| {
+                Ok(Procedure {
+                    body: Program::resolve(proc.body.clone(), &labelled)?,
+                    ..proc.clone()
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let contents = Program::resolve(contents, &labelled)?;
+
+        Ok(Self {
+            procedures,
             contents,
             len: next - 1,
-        }
+        })
+    }
+
+    /// creates a program with the procedures of this one and a different main statement
+    pub fn with_contents(&self, contents: Statement) -> Self {
+        Self::with_procedures(self.procedures.clone(), contents).unwrap()
     }
 
     /// returns the block at a given label in the program
     pub fn at(&self, label: Label) -> Option<Block> {
-        Program::stmt_at(&self.contents, label)
+        self.procedures
+            .iter()
+            .find_map(|proc| match label {
+                _ if label == proc.entry => Some(proc.entry_block()),
+                _ if label == proc.exit => Some(proc.exit_block()),
+                _ => Program::stmt_at(&proc.body, label),
+            })
+            .or_else(|| Program::stmt_at(&self.contents, label))
     }
 
+    /// returns the procedure whose body contains a given label, or `None` if it is part of the
+    /// main statement
+    pub fn procedure_at(&self, label: Label) -> Option<&Procedure> {
+        self.procedures
+            .iter()
+            .find(|proc| (proc.entry..=proc.exit).contains(&label))
+    }
+
+    /// see [`functions::init_label`]
     pub fn init_label(&self) -> Label {
-        1
+        functions::init_label(&self.contents)
     }
-    pub fn final_labels(&self) -> HashSet<Label> {
+    /// see [`functions::final_labels`]
+    pub fn final_labels(&self) -> BTreeSet<Label> {
         functions::final_labels(&self.contents)
     }
-    pub fn flow(&self) -> HashSet<(Label, Label)> {
-        functions::flow(&self.contents)
+    /// see [`functions::flow`] and [`functions::proc_flow`]
+    pub fn flow(&self) -> BTreeSet<(Label, Label)> {
+        self.procedures
+            .iter()
+            .flat_map(functions::proc_flow)
+            .chain(functions::flow(&self.contents))
+            .collect()
+    }
+    /// see [`functions::flow_r`]
+    pub fn flow_r(&self) -> BTreeSet<(Label, Label)> {
+        self.flow().iter().map(|(l, l_p)| (*l_p, *l)).collect()
+    }
+    /// see [`functions::inter_flow`]
+    pub fn inter_flow(&self) -> BTreeSet<(Label, Label, Label, Label)> {
+        self.statements().flat_map(functions::inter_flow).collect()
     }
-    pub fn flow_r(&self) -> HashSet<(Label, Label)> {
-        functions::flow_r(&self.contents)
+    /// see [`functions::true_flow`]
+    pub fn true_flow(&self) -> BTreeSet<(Label, Label)> {
+        self.statements().flat_map(functions::true_flow).collect()
     }
-    pub fn blocks(&self) -> HashSet<Block> {
-        functions::blocks(&self.contents)
+    /// see [`functions::loop_heads`]; the entries of procedures are included as well, as every
+    /// cycle through a recursive call passes through one
+    pub fn loop_heads(&self) -> BTreeSet<Label> {
+        self.statements()
+            .flat_map(functions::loop_heads)
+            .chain(self.procedures.iter().map(|proc| proc.entry))
+            .collect()
+    }
+    /// see [`functions::blocks`] and [`functions::proc_blocks`]
+    pub fn blocks(&self) -> BTreeSet<Block> {
+        self.procedures
+            .iter()
+            .flat_map(functions::proc_blocks)
+            .chain(functions::blocks(&self.contents))
+            .collect()
+    }
+    /// see [`functions::free_vars`]
+    pub fn free_vars(&self) -> BTreeSet<Variable> {
+        self.statements().flat_map(functions::free_vars).collect()
+    }
+    /// see [`functions::aexp_star`]
+    pub fn aexp_star(&self) -> BTreeSet<AExp> {
+        self.statements().flat_map(functions::aexp_star).collect()
+    }
+
+    /// the bodies of the procedures followed by the main statement (internal use)
+    fn statements(&self) -> impl Iterator<Item = &Statement> {
+        self.procedures
+            .iter()
+            .map(|proc| &proc.body)
+            .chain([&self.contents])
+    }
+
+    /// fills in the labels and parameters of the procedures called in a statement (internal
+    /// use)
+    fn resolve(stmt: Statement, procedures: &[Procedure]) -> Result<Statement, &'static str> {
+        Ok(match stmt {
+            Statement::Atom(_) => stmt,
+
+            Statement::Sequence(stmt1, stmt2) => Statement::Sequence(
+                Box::new(Program::resolve(*stmt1, procedures)?),
+                Box::new(Program::resolve(*stmt2, procedures)?),
+            ),
+
+            Statement::IfThenElse(test, stmt1, stmt2) => Statement::IfThenElse(
+                test,
+                Box::new(Program::resolve(*stmt1, procedures)?),
+                Box::new(Program::resolve(*stmt2, procedures)?),
+            ),
+
+            Statement::While(test, stmt1) => {
+                Statement::While(test, Box::new(Program::resolve(*stmt1, procedures)?))
+            }
+
+            Statement::Call(call) => {
+                let proc = procedures
+                    .iter()
+                    .find(|proc| proc.name == call.name)
+                    .ok_or("a call to a declared procedure")?;
+
+                // all arguments, as the results may not have been split off yet
+                let mut args: Vec<AExp> = call.args.into_iter().collect();
+                args.extend(call.results.into_iter().map(AExp::Variable));
+                if args.len() != proc.val.len() + proc.res.len() {
+                    return Err("as many arguments as parameters");
+                }
+
+                let results = args
+                    .split_off(proc.val.len())
+                    .into_iter()
+                    .map(|arg| match arg {
+                        AExp::Variable(var) => Ok(var),
+                        _ => Err("a variable as argument to a result parameter"),
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Statement::Call(CallBlock {
+                    args,
+                    results,
+                    entry: proc.entry,
+                    exit: proc.exit,
+                    val: proc.val.clone(),
+                    res: proc.res.clone(),
+                    ..call
+                })
+            }
+        })
     }
 
     /// relabels a statement and returns it together with a following label (internal use)
@@ -52,10 +218,21 @@ impl Program {
             Statement::Atom(block) => (
                 Statement::Atom(match block {
                     Block::Assignment(AssignmentBlock { var, expr, .. }) => {
-                        Block::assignment(start.clone(), var, expr.clone())
+                        Block::assignment(start, var, expr.clone())
+                    }
+                    Block::ArrayAssignment(ArrayAssignmentBlock {
+                        array, index, expr, ..
+                    }) => Block::array_assignment(start, array, index, expr),
+                    Block::Store(StoreBlock { ptr, expr, .. }) => Block::store(start, ptr, expr),
+                    Block::Skip(SkipBlock { .. }) => Block::skip(start),
+                    Block::Read(ReadBlock { var, .. }) => Block::read(start, var),
+                    Block::Write(WriteBlock { expr, .. }) => Block::write(start, expr),
+                    Block::Test(TestBlock { expr, .. }) => Block::test(start, expr.clone()),
+                    Block::Entry(_) | Block::Exit(_) | Block::Call(_) | Block::Return(_) => {
+                        unreachable!(
+                            "only assignments, stores, skip, read, write and tests are atomic statements"
+                        )
                     }
-                    Block::Skip(SkipBlock { .. }) => Block::skip(start.clone()),
-                    Block::Test(TestBlock { expr, .. }) => Block::test(start.clone(), expr.clone()),
                 }),
                 start + 1,
             ),
@@ -99,6 +276,15 @@ impl Program {
 
                 (Statement::While(new_test, Box::new(new_stmt1)), next)
             }
+
+            Statement::Call(call) => (
+                Statement::Call(CallBlock {
+                    label: start,
+                    ret: start + 1,
+                    ..call
+                }),
+                start + 2,
+            ),
         }
     }
 
@@ -150,12 +336,61 @@ impl Program {
 
                 None
             }
+
+            Statement::Call(call) => {
+                if call.label == label {
+                    return Some(Block::Call(call.clone()));
+                }
+                if call.ret == label {
+                    return Some(Block::Return(call.clone()));
+                }
+
+                None
+            }
         }
     }
 }
 
 impl Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.contents.fmt(f)
+        if self.procedures.is_empty() {
+            return self.contents.fmt(f);
+        }
+
+        write!(f, "begin ")?;
+        for proc in &self.procedures {
+            write!(f, "{}; ", proc)?;
+        }
+        write!(f, "{} end", self.contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser;
+
+    /// the reason the program is rejected, as reported by the parser
+    fn error(input: &str) -> String {
+        parser::parse(input).unwrap_err().expected.to_string()
+    }
+
+    #[test]
+    fn procedures_are_declared_once() {
+        let input = "proc p(val x) is skip end; proc p(val y) is skip end; call p(1) ";
+        assert!(error(input).contains("distinct procedure names"));
+        assert!(!error(input).contains("as many arguments as parameters"));
+    }
+
+    #[test]
+    fn calls_match_the_declaration() {
+        let proc = "proc p(val x, res y) is y := x end; ";
+        assert!(error(&format!("{proc}call p(1) ")).contains("as many arguments as parameters"));
+        assert!(
+            error(&format!("{proc}call p(1, 2, z) ")).contains("as many arguments as parameters")
+        );
+        assert!(error(&format!("{proc}call p(1, 2) "))
+            .contains("a variable as argument to a result parameter"));
+        assert!(error(&format!("{proc}call q(1, z) ")).contains("a call to a declared procedure"));
+        assert!(parser::parse(&format!("{proc}call p(1, z) ")).is_ok());
     }
 }
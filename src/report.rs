@@ -0,0 +1,328 @@
+use serde_json::{json, Value};
+
+use crate::{
+    algorithm::{call_strings, Solver},
+    analysis::{
+        ac::AvailableCopies,
+        ae::AvailableExpressions,
+        chains::{du_chains, ud_chains},
+        cp::{constant_tests, ConstantPropagation},
+        ds::DetectionOfSigns,
+        ilv::InterproceduralLiveVariables,
+        interval::Intervals,
+        lv::LiveVariables,
+        pt::PointsTo,
+        rd::ReachingDefinitions,
+        slv::StronglyLiveVariables,
+        vb::VeryBusyExpressions,
+        Analysis, MonotoneFramework,
+    },
+    output::{dot, json, latex, text, Format, Present},
+    program::Program,
+};
+
+/// the analyses whose results can be reported
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnalysisKind {
+    /// live variables
+    Lv,
+    /// interprocedural live variables
+    Ilv,
+    /// strongly live variables
+    Slv,
+    /// reaching definitions
+    Rd,
+    /// available expressions
+    Ae,
+    /// very busy expressions
+    Vb,
+    /// constant propagation
+    Cp,
+    /// intervals
+    Int,
+    /// detection of signs
+    Ds,
+    /// available copies
+    Ac,
+    /// use-definition and definition-use chains
+    Chains,
+    /// points-to sets
+    Pt,
+}
+impl AnalysisKind {
+    /// the short name the results of the analysis are labelled with
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lv => "lv",
+            Self::Ilv => "ilv",
+            Self::Slv => "slv",
+            Self::Rd => "rd",
+            Self::Ae => "ae",
+            Self::Vb => "vb",
+            Self::Cp => "cp",
+            Self::Int => "int",
+            Self::Ds => "ds",
+            Self::Ac => "ac",
+            Self::Chains => "chains",
+            Self::Pt => "pt",
+        }
+    }
+}
+
+/// how the analyses are solved and their results presented
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Options {
+    pub solver: Solver,
+    /// also solve the analyses context-sensitively, with call strings of at most this length
+    pub call_string_depth: Option<usize>,
+    pub format: Format,
+}
+
+/// the results of a single analysis, either as formatted output or as the value to include in
+/// a JSON document
+enum Results {
+    Output(String),
+    Json(Value),
+}
+
+/// solves the analyses of a program and renders their results; in JSON, all of them are
+/// collected into a single document, otherwise each is followed by a blank line, and in text
+/// headed by the name of the analysis if there are several
+pub fn render(program: &Program, analyses: &[AnalysisKind], options: &Options) -> String {
+    let results = analyses
+        .iter()
+        .map(|kind| (kind.name(), results(program, *kind, options)));
+
+    if options.format == Format::Json {
+        let results = results
+            .map(|(name, results)| match results {
+                Results::Json(value) => (name, value),
+                Results::Output(_) => unreachable!("JSON results are always values"),
+            })
+            .collect();
+        return format!("{}\n", json::document(program, results));
+    }
+
+    let headings = options.format == Format::Text && analyses.len() > 1;
+    results
+        .map(|(name, results)| match results {
+            Results::Output(output) if headings => format!("Analysis: {name}\n{output}\n"),
+            Results::Output(output) => format!("{output}\n"),
+            Results::Json(_) => unreachable!("only JSON results are values"),
+        })
+        .collect()
+}
+
+fn results(program: &Program, kind: AnalysisKind, options: &Options) -> Results {
+    let name = kind.name();
+    match kind {
+        AnalysisKind::Lv => solve(program, name, &LiveVariables::new(program), options),
+        AnalysisKind::Ilv => solve(
+            program,
+            name,
+            &InterproceduralLiveVariables::new(program),
+            options,
+        ),
+        AnalysisKind::Slv => solve(program, name, &StronglyLiveVariables::new(program), options),
+        AnalysisKind::Rd => solve(program, name, &ReachingDefinitions::new(program), options),
+        AnalysisKind::Ae => solve(program, name, &AvailableExpressions::new(program), options),
+        AnalysisKind::Vb => solve(program, name, &VeryBusyExpressions::new(program), options),
+        AnalysisKind::Int => solve(program, name, &Intervals::new(program), options),
+        AnalysisKind::Ds => solve(program, name, &DetectionOfSigns::new(program), options),
+        AnalysisKind::Ac => solve(program, name, &AvailableCopies::new(program), options),
+
+        // the tests with a known outcome are reported along with the values
+        AnalysisKind::Cp => {
+            let cp = ConstantPropagation::new(program);
+            let cpa = options.solver.run(program, &cp);
+            let tests = constant_tests(program, &cpa);
+
+            match (present(program, name, &cp, &cpa, options), options.format) {
+                (Results::Output(output), Format::Text) => {
+                    Results::Output(output + &text::render_constant_tests(&tests))
+                }
+                (Results::Json(mut results), _) => {
+                    results["constant_tests"] = json::constant_tests(&tests);
+                    Results::Json(results)
+                }
+                (results, _) => results,
+            }
+        }
+
+        // neither chains nor points-to sets are values at labels, so the graph is not annotated
+        AnalysisKind::Chains => {
+            let rda = options
+                .solver
+                .run(program, &ReachingDefinitions::new(program));
+            let ud = ud_chains(program, &rda);
+            let du = du_chains(program, &ud);
+
+            match options.format {
+                Format::Text => Results::Output(text::render_chains(&ud, &du)),
+                Format::Json => Results::Json(json::chains(&ud, &du)),
+                Format::Dot => Results::Output(dot::render(program, None)),
+                Format::Latex => {
+                    Results::Output(latex::render_lines(&text::render_chains(&ud, &du)))
+                }
+            }
+        }
+
+        AnalysisKind::Pt => {
+            let pt = PointsTo::new(program);
+
+            match options.format {
+                Format::Text => Results::Output(text::render_points_to(&pt)),
+                Format::Json => Results::Json(json::points_to(&pt)),
+                Format::Dot => Results::Output(dot::render(program, None)),
+                Format::Latex => Results::Output(latex::render_lines(&text::render_points_to(&pt))),
+            }
+        }
+    }
+}
+
+/// solves an instance of the monotone framework using the selected solver and presents the
+/// results
+fn solve<F: MonotoneFramework>(
+    program: &Program,
+    name: &str,
+    framework: &F,
+    options: &Options,
+) -> Results
+where
+    F::Lattice: Present,
+{
+    let analysis = options.solver.run(program, framework);
+    present(program, name, framework, &analysis, options)
+}
+
+/// presents the results of an analysis, together with the results for each call string if
+/// a call-string depth is given
+fn present<F: MonotoneFramework>(
+    program: &Program,
+    name: &str,
+    framework: &F,
+    analysis: &Analysis<F::Lattice>,
+    options: &Options,
+) -> Results
+where
+    F::Lattice: Present,
+{
+    let contexts = options
+        .call_string_depth
+        .map(|depth| call_strings::run(program, framework, depth));
+
+    match (options.format, contexts) {
+        (Format::Text, None) => Results::Output(text::render(program, analysis)),
+        (Format::Text, Some(contexts)) => Results::Output(format!(
+            "Context-insensitive:\n{}\nCall strings (k = {}):\n{}",
+            text::render(program, analysis),
+            contexts.depth,
+            text::render_contexts(program, &contexts),
+        )),
+
+        (Format::Json, contexts) => {
+            let mut results = json!({ "results": json::analysis(program, analysis) });
+            if let Some(contexts) = contexts {
+                results["call_strings"] = json::contexts(program, &contexts);
+            }
+            Results::Json(results)
+        }
+
+        // a single value per label fits into a graph or a table, so the values of all call
+        // strings are joined
+        (Format::Dot | Format::Latex, contexts) => {
+            let merged = contexts.map(|contexts| contexts.merge(framework));
+            let analysis = merged.as_ref().unwrap_or(analysis);
+
+            if options.format == Format::Dot {
+                let annotations = dot::annotations(program, analysis);
+                Results::Output(dot::render(program, Some(&annotations)))
+            } else {
+                Results::Output(latex::render(program, name, analysis))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algorithm::mfp, parser};
+
+    fn options(format: Format) -> Options {
+        Options {
+            solver: Solver::Mfp,
+            call_string_depth: None,
+            format,
+        }
+    }
+
+    #[test]
+    fn text_results_are_followed_by_a_blank_line() {
+        let program = parser::parse("x := 2; if x > 1 then y := x else skip endif ").unwrap();
+        let lva = mfp::run(&program, &LiveVariables::new(&program));
+
+        assert_eq!(
+            render(&program, &[AnalysisKind::Lv], &options(Format::Text)),
+            format!("{}\n", text::render(&program, &lva))
+        );
+        assert!(
+            render(&program, &[AnalysisKind::Cp], &options(Format::Text))
+                .ends_with("[x > 1]² is always true\n\n")
+        );
+    }
+
+    #[test]
+    fn json_results_are_collected_into_one_document() {
+        let program = parser::parse("x := 2; if x > 1 then y := x else skip endif ").unwrap();
+        let output = render(&program, &[AnalysisKind::Cp], &options(Format::Json));
+        let document: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(document["program"]["labels"], json!([1, 2, 3, 4]));
+        assert_eq!(
+            document["analyses"]["cp"]["constant_tests"],
+            json!([{ "label": 2, "value": true }])
+        );
+        assert_eq!(
+            document["analyses"]["cp"]["results"][0]["exit"]["x"],
+            json!(2)
+        );
+
+        let output = render(
+            &program,
+            &[AnalysisKind::Lv, AnalysisKind::Rd],
+            &options(Format::Json),
+        );
+        let document: Value = serde_json::from_str(&output).unwrap();
+        let analyses = document["analyses"].as_object().unwrap();
+        assert_eq!(analyses.keys().collect::<Vec<_>>(), ["lv", "rd"]);
+    }
+
+    #[test]
+    fn several_text_results_are_headed_by_their_names() {
+        let program = parser::parse("x := 2; y := x ").unwrap();
+        let options = options(Format::Text);
+        let lv = render(&program, &[AnalysisKind::Lv], &options);
+        let rd = render(&program, &[AnalysisKind::Rd], &options);
+
+        assert_eq!(
+            render(&program, &[AnalysisKind::Lv, AnalysisKind::Rd], &options),
+            format!("Analysis: lv\n{lv}Analysis: rd\n{rd}")
+        );
+    }
+
+    #[test]
+    fn live_and_strongly_live_variables_side_by_side() {
+        let program = parser::parse("x := 1; y := x; z := 2; write z ").unwrap();
+        let output = render(
+            &program,
+            &[AnalysisKind::Lv, AnalysisKind::Slv],
+            &options(Format::Text),
+        );
+
+        // x is live before y := x, but not strongly live as y is dead
+        let (lv, slv) = output.split_once("Analysis: slv\n").unwrap();
+        assert!(lv.contains("2: entry={x}, exit={}"));
+        assert!(slv.contains("2: entry={}, exit={}"));
+    }
+}
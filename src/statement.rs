@@ -1,6 +1,9 @@
-use std::fmt::Display;
+use std::{collections::BTreeSet, fmt::Display};
 
-use crate::block::{Block, TestBlock};
+use crate::{
+    block::{Block, CallBlock, ProcBlock, TestBlock},
+    expression::{Label, Variable},
+};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Statement {
@@ -18,6 +21,55 @@ pub enum Statement {
 
     /// while \[b\] do S
     While(TestBlock, Box<Statement>),
+
+    /// \[call p(a, z)\]
+    Call(CallBlock),
+}
+
+/// proc p(val x, res y) is S end
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Procedure {
+    pub name: String,
+    /// the value parameters
+    pub val: Vec<Variable>,
+    /// the result parameters
+    pub res: Vec<Variable>,
+    /// the label of `is`
+    pub entry: Label,
+    /// the label of `end`
+    pub exit: Label,
+    pub body: Statement,
+}
+impl Procedure {
+    pub fn new(name: String, val: Vec<Variable>, res: Vec<Variable>, body: Statement) -> Self {
+        Self {
+            name,
+            val,
+            res,
+            entry: 0,
+            exit: 0,
+            body,
+        }
+    }
+
+    /// the formal parameters, which are local to the body
+    pub fn locals(&self) -> BTreeSet<Variable> {
+        self.val.iter().chain(&self.res).copied().collect()
+    }
+
+    /// the `is` and `end` blocks of the declaration
+    pub fn entry_block(&self) -> Block {
+        Block::Entry(ProcBlock {
+            label: self.entry,
+            name: self.name.clone(),
+        })
+    }
+    pub fn exit_block(&self) -> Block {
+        Block::Exit(ProcBlock {
+            label: self.exit,
+            name: self.name.clone(),
+        })
+    }
 }
 
 impl Display for Statement {
@@ -44,7 +96,33 @@ impl Display for Statement {
                 Self::While(test, stmt1) => {
                     format!("while {} do {} enddo", Block::Test(test.clone()), stmt1,)
                 }
+
+                Self::Call(call) => call.to_string(),
             }
         )
     }
 }
+
+impl Display for Procedure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut params = vec![];
+        if !self.val.is_empty() {
+            let val: Vec<String> = self.val.iter().map(Variable::to_string).collect();
+            params.push(format!("val {}", val.join(", ")));
+        }
+        if !self.res.is_empty() {
+            let res: Vec<String> = self.res.iter().map(Variable::to_string).collect();
+            params.push(format!("res {}", res.join(", ")));
+        }
+
+        write!(
+            f,
+            "proc {}({}) {} {} {}",
+            self.name,
+            params.join(", "),
+            self.entry_block(),
+            self.body,
+            self.exit_block()
+        )
+    }
+}
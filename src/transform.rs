@@ -0,0 +1,54 @@
+use crate::{block::Block, program::Program, statement::Statement};
+
+pub mod cf;
+pub mod copy;
+pub mod cse;
+pub mod dae;
+
+/// the transformations a program can be optimized with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transformation {
+    /// dead assignment elimination
+    Dae,
+    /// constant folding
+    Cf,
+    /// copy propagation
+    Copy,
+    /// common subexpression elimination
+    Cse,
+}
+impl Transformation {
+    /// applies the transformation, or returns why it cannot be applied to the program
+    pub fn run(self, program: &Program) -> Result<Program, &'static str> {
+        match self {
+            Self::Dae => dae::run(program),
+            Self::Cf => cf::run(program),
+            Self::Copy => copy::run(program),
+            Self::Cse => cse::run(program),
+        }
+    }
+}
+
+/// joins the remains of two statements in sequence, where `None` stands for a removed statement
+fn sequence(stmt1: Option<Statement>, stmt2: Option<Statement>) -> Option<Statement> {
+    match (stmt1, stmt2) {
+        (Some(stmt1), Some(stmt2)) => Some(Statement::Sequence(Box::new(stmt1), Box::new(stmt2))),
+        (stmt, None) | (None, stmt) => stmt,
+    }
+}
+
+/// checks whether the transformations can be applied to a program; the analyses they are
+/// based on identify the parameters of procedures with the global variables of the same name,
+/// so programs declaring procedures are rejected
+fn supported(program: &Program) -> Result<(), &'static str> {
+    if program.procedures.is_empty() {
+        Ok(())
+    } else {
+        Err("programs declaring procedures are not supported")
+    }
+}
+
+/// turns a removed statement into `skip` where a statement is required
+fn or_skip(stmt: Option<Statement>) -> Statement {
+    stmt.unwrap_or(Statement::Atom(Block::skip(0)))
+}
@@ -0,0 +1,217 @@
+#![allow(dead_code)]
+use crate::{
+    algorithm,
+    analysis::cp::{CPAnalysis, CPState, ConstValue, ConstantPropagation},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, StoreBlock, TestBlock, WriteBlock},
+    expression::{eval_arithmetic_op, eval_relational_op, AExp, BExp, Variable},
+    program::Program,
+    statement::Statement,
+    transform::{or_skip, sequence, supported},
+};
+
+/// folds operations on numbers into a single number, as far as possible
+pub fn fold_aexp(expr: &AExp) -> AExp {
+    match expr {
+        AExp::Variable(_) | AExp::Number(_) => expr.clone(),
+        AExp::AddressOf(_) | AExp::Deref(_) | AExp::New => expr.clone(),
+        AExp::ArithmeticOp(lhs, op, rhs) => match (fold_aexp(lhs), fold_aexp(rhs)) {
+            (AExp::Number(a), AExp::Number(b)) => match eval_arithmetic_op(a, op, b) {
+                Some(val) => AExp::Number(val),
+                None => AExp::ArithmeticOp(
+                    Box::new(AExp::Number(a)),
+                    op.clone(),
+                    Box::new(AExp::Number(b)),
+                ),
+            },
+            (lhs, rhs) => AExp::ArithmeticOp(Box::new(lhs), op.clone(), Box::new(rhs)),
+        },
+        AExp::ArrayElement(array, index) => AExp::ArrayElement(*array, Box::new(fold_aexp(index))),
+    }
+}
+
+/// folds comparisons of numbers into `true` or `false` and simplifies the resulting expression
+pub fn fold_bexp(expr: &BExp) -> BExp {
+    match expr {
+        BExp::True | BExp::False => expr.clone(),
+
+        BExp::Not(inner) => match fold_bexp(inner) {
+            BExp::True => BExp::False,
+            BExp::False => BExp::True,
+            inner => BExp::Not(Box::new(inner)),
+        },
+
+        BExp::BooleanOp(lhs, op, rhs) => match (fold_bexp(lhs), op.as_str(), fold_bexp(rhs)) {
+            (BExp::True, "&&", other) | (other, "&&", BExp::True) => other,
+            (BExp::False, "||", other) | (other, "||", BExp::False) => other,
+            (BExp::False, "&&", _) | (_, "&&", BExp::False) => BExp::False,
+            (BExp::True, "||", _) | (_, "||", BExp::True) => BExp::True,
+            (lhs, _, rhs) => BExp::BooleanOp(Box::new(lhs), op.clone(), Box::new(rhs)),
+        },
+
+        BExp::RelationalOp(lhs, op, rhs) => match (fold_aexp(lhs), fold_aexp(rhs)) {
+            (AExp::Number(a), AExp::Number(b)) => {
+                if eval_relational_op(a, op, b) {
+                    BExp::True
+                } else {
+                    BExp::False
+                }
+            }
+            (lhs, rhs) => BExp::RelationalOp(lhs, op.clone(), rhs),
+        },
+    }
+}
+
+/// replaces every variable that is known to be constant by its value
+fn constants(state: &CPState) -> impl Fn(&Variable) -> Option<AExp> + '_ {
+    |var| match state.get(var) {
+        Some(ConstValue::Const(val)) => Some(AExp::Number(*val)),
+        _ => None,
+    }
+}
+
+/// substitutes the constants found by `cpa` into every expression and folds it; tests that
+/// become `true` or `false` cause their if/while statements to be simplified
+pub fn fold(program: &Program, cpa: &CPAnalysis) -> Program {
+    program.with_contents(or_skip(fold_stmt(program.contents.clone(), cpa)))
+}
+
+/// repeatedly folds constants until nothing changes, as removing a branch may reveal new ones
+pub fn run(program: &Program) -> Result<Program, &'static str> {
+    supported(program)?;
+
+    let mut program = program.clone();
+    loop {
+        let cpa = algorithm::mfp::run(&program, &ConstantPropagation::new(&program));
+        let next = fold(&program, &cpa);
+
+        if next == program {
+            return Ok(program);
+        }
+
+        program = next;
+    }
+}
+
+fn fold_test(test: TestBlock, cpa: &CPAnalysis) -> TestBlock {
+    TestBlock {
+        label: test.label,
+        expr: fold_bexp(&test.expr.substitute(&constants(&cpa.entry[&test.label]))),
+    }
+}
+
+/// returns the folded statement, or `None` if nothing remains of it
+fn fold_stmt(stmt: Statement, cpa: &CPAnalysis) -> Option<Statement> {
+    match stmt {
+        Statement::Atom(Block::Assignment(AssignmentBlock { label, var, expr })) => {
+            let expr = fold_aexp(&expr.substitute(&constants(&cpa.entry[&label])));
+            Some(Statement::Atom(Block::assignment(label, var, expr)))
+        }
+
+        Statement::Atom(Block::ArrayAssignment(ArrayAssignmentBlock {
+            label,
+            array,
+            index,
+            expr,
+        })) => {
+            let fold = |expr: AExp| fold_aexp(&expr.substitute(&constants(&cpa.entry[&label])));
+            Some(Statement::Atom(Block::array_assignment(
+                label,
+                array,
+                fold(index),
+                fold(expr),
+            )))
+        }
+
+        Statement::Atom(Block::Store(StoreBlock { label, ptr, expr })) => {
+            let expr = fold_aexp(&expr.substitute(&constants(&cpa.entry[&label])));
+            Some(Statement::Atom(Block::store(label, ptr, expr)))
+        }
+
+        Statement::Atom(Block::Write(WriteBlock { label, expr })) => {
+            let expr = fold_aexp(&expr.substitute(&constants(&cpa.entry[&label])));
+            Some(Statement::Atom(Block::write(label, expr)))
+        }
+
+        Statement::Atom(Block::Test(test)) => {
+            Some(Statement::Atom(Block::Test(fold_test(test, cpa))))
+        }
+
+        Statement::Atom(block) => Some(Statement::Atom(block)),
+
+        Statement::Sequence(stmt1, stmt2) => {
+            sequence(fold_stmt(*stmt1, cpa), fold_stmt(*stmt2, cpa))
+        }
+
+        Statement::IfThenElse(test, stmt1, stmt2) => {
+            let test = fold_test(test, cpa);
+            match test.expr {
+                BExp::True => Some(or_skip(fold_stmt(*stmt1, cpa))),
+                BExp::False => Some(or_skip(fold_stmt(*stmt2, cpa))),
+                _ => Some(Statement::IfThenElse(
+                    test,
+                    Box::new(or_skip(fold_stmt(*stmt1, cpa))),
+                    Box::new(or_skip(fold_stmt(*stmt2, cpa))),
+                )),
+            }
+        }
+
+        Statement::While(test, stmt1) => {
+            let test = fold_test(test, cpa);
+            match test.expr {
+                BExp::False => None,
+                _ => Some(Statement::While(
+                    test,
+                    Box::new(or_skip(fold_stmt(*stmt1, cpa))),
+                )),
+            }
+        }
+
+        Statement::Call(call) => Some(Statement::Call(call)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    /// compares the programs as printed, as the transformation may nest sequences differently
+    fn assert_folded(input: &str, expected: &str) {
+        let program = parser::parse(input).unwrap();
+        assert_eq!(
+            run(&program).unwrap().to_string(),
+            parser::parse(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn constant_branches_are_chosen() {
+        assert_folded(
+            "if false then x := 1 else x := 2 endif; write x ",
+            "x := 2; write 2 ",
+        );
+        assert_folded(
+            "if true then x := 1 else x := 2 endif; write x ",
+            "x := 1; write 1 ",
+        );
+        assert_folded(
+            "x := 1; while false do x := 2 enddo; write x ",
+            "x := 1; write 1 ",
+        );
+    }
+
+    #[test]
+    fn constants_are_folded_through_arithmetic() {
+        // y is only known to be 7 > 5 after x has been substituted and x * 3 + 1 folded
+        assert_folded(
+            "x := 2; y := x * 3 + 1; if y > 5 then z := y else z := 0 endif ",
+            "x := 2; y := 7; z := 7 ",
+        );
+    }
+
+    #[test]
+    fn tests_that_are_not_constant_are_kept() {
+        let input = "read x; if x > 0 then y := 1 else y := 2 endif; write y ";
+        assert_folded(input, input);
+    }
+}
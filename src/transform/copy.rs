@@ -0,0 +1,160 @@
+#![allow(dead_code)]
+use std::collections::BTreeSet;
+
+use crate::{
+    algorithm,
+    analysis::ac::{ACAnalysis, AvailableCopies, CopyAssignment},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, StoreBlock, TestBlock, WriteBlock},
+    expression::{AExp, Variable},
+    program::Program,
+    statement::Statement,
+    transform::supported,
+};
+
+/// replaces every variable x by y if the copy x := y is available
+fn copies(ac: &BTreeSet<CopyAssignment>) -> impl Fn(&Variable) -> Option<AExp> + '_ {
+    |var| {
+        ac.iter()
+            .find(|(x, _)| x == var)
+            .map(|(_, y)| AExp::Variable(*y))
+    }
+}
+
+/// replaces uses of variables by the variables they are copies of, according to `aca`
+pub fn propagate(program: &Program, aca: &ACAnalysis) -> Program {
+    program.with_contents(propagate_stmt(program.contents.clone(), aca))
+}
+
+/// repeatedly propagates copies until nothing changes, so that chains of copies are followed
+/// back to their origin
+pub fn run(program: &Program) -> Result<Program, &'static str> {
+    supported(program)?;
+
+    let mut program = program.clone();
+    loop {
+        let aca = algorithm::mfp::run(&program, &AvailableCopies::new(&program));
+        let next = propagate(&program, &aca);
+
+        if next == program {
+            return Ok(program);
+        }
+
+        program = next;
+    }
+}
+
+fn propagate_test(test: TestBlock, aca: &ACAnalysis) -> TestBlock {
+    TestBlock {
+        label: test.label,
+        expr: test.expr.substitute(&copies(&aca.entry[&test.label])),
+    }
+}
+
+fn propagate_stmt(stmt: Statement, aca: &ACAnalysis) -> Statement {
+    match stmt {
+        Statement::Atom(Block::Assignment(AssignmentBlock { label, var, expr })) => {
+            let expr = expr.substitute(&copies(&aca.entry[&label]));
+            Statement::Atom(Block::assignment(label, var, expr))
+        }
+
+        Statement::Atom(Block::ArrayAssignment(ArrayAssignmentBlock {
+            label,
+            array,
+            index,
+            expr,
+        })) => {
+            let ac = copies(&aca.entry[&label]);
+            Statement::Atom(Block::array_assignment(
+                label,
+                array,
+                index.substitute(&ac),
+                expr.substitute(&ac),
+            ))
+        }
+
+        Statement::Atom(Block::Store(StoreBlock { label, ptr, expr })) => {
+            let expr = expr.substitute(&copies(&aca.entry[&label]));
+            Statement::Atom(Block::store(label, ptr, expr))
+        }
+
+        Statement::Atom(Block::Write(WriteBlock { label, expr })) => {
+            let expr = expr.substitute(&copies(&aca.entry[&label]));
+            Statement::Atom(Block::write(label, expr))
+        }
+
+        Statement::Atom(Block::Test(test)) => {
+            Statement::Atom(Block::Test(propagate_test(test, aca)))
+        }
+
+        Statement::Atom(block) => Statement::Atom(block),
+
+        Statement::Sequence(stmt1, stmt2) => Statement::Sequence(
+            Box::new(propagate_stmt(*stmt1, aca)),
+            Box::new(propagate_stmt(*stmt2, aca)),
+        ),
+
+        Statement::IfThenElse(test, stmt1, stmt2) => Statement::IfThenElse(
+            propagate_test(test, aca),
+            Box::new(propagate_stmt(*stmt1, aca)),
+            Box::new(propagate_stmt(*stmt2, aca)),
+        ),
+
+        Statement::While(test, stmt1) => Statement::While(
+            propagate_test(test, aca),
+            Box::new(propagate_stmt(*stmt1, aca)),
+        ),
+
+        Statement::Call(call) => Statement::Call(call),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser, transform::dae};
+
+    /// compares the programs as printed, as the transformation may nest sequences differently
+    fn assert_propagated(input: &str, expected: &str) {
+        let program = parser::parse(input).unwrap();
+        assert_eq!(
+            run(&program).unwrap().to_string(),
+            parser::parse(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn copies_are_propagated_if_available_on_all_paths() {
+        let one_path = "read y; read c; if c > 0 then x := y else x := 1 endif; write x ";
+        assert_propagated(one_path, one_path);
+        assert_propagated(
+            "read y; read c; if c > 0 then x := y else x := y endif; write x ",
+            "read y; read c; if c > 0 then x := y else x := y endif; write y ",
+        );
+    }
+
+    #[test]
+    fn copies_are_not_propagated_past_redefinitions() {
+        let input = "read y; x := y; y := 2; write x ";
+        assert_propagated(input, input);
+    }
+
+    #[test]
+    fn chains_of_copies_are_followed() {
+        assert_propagated(
+            "read a; b := a; c := b; write c ",
+            "read a; b := a; c := a; write a ",
+        );
+    }
+
+    #[test]
+    fn propagated_copies_are_removed_by_dae() {
+        let program = parser::parse("read y; x := y; z := x + 1; write z ").unwrap();
+        let program = dae::run(&run(&program).unwrap()).unwrap();
+        assert_eq!(
+            program.to_string(),
+            parser::parse("read y; z := y + 1; write z ")
+                .unwrap()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,250 @@
+#![allow(dead_code)]
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    algorithm,
+    analysis::ae::{AEAnalysis, AvailableExpressions},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block, StoreBlock, TestBlock, WriteBlock},
+    expression::{AExp, FreshVariables, Label, Variable},
+    program::Program,
+    statement::Statement,
+    transform::{sequence, supported},
+};
+
+/// the temporaries holding the value of each recomputed expression
+type Temporaries = BTreeMap<AExp, Variable>;
+
+/// the non-trivial arithmetic expressions computed by a block
+fn computed(block: &Block) -> BTreeSet<AExp> {
+    match block {
+        Block::Assignment(AssignmentBlock { expr, .. }) => expr.subexpressions(),
+        Block::ArrayAssignment(ArrayAssignmentBlock { index, expr, .. }) => index
+            .subexpressions()
+            .union(&expr.subexpressions())
+            .cloned()
+            .collect(),
+        Block::Store(StoreBlock { expr, .. }) | Block::Write(WriteBlock { expr, .. }) => {
+            expr.subexpressions()
+        }
+        Block::Test(TestBlock { expr, .. }) => expr.subexpressions(),
+        Block::Skip(_) | Block::Read(_) => [].into(),
+        Block::Entry(_) | Block::Exit(_) => [].into(),
+        Block::Call(_) | Block::Return(_) => block
+            .bindings()
+            .iter()
+            .flat_map(|(_, expr)| expr.subexpressions())
+            .collect(),
+    }
+}
+
+/// replaces the largest subexpressions that have a temporary by that temporary
+fn replace(expr: &AExp, temps: &Temporaries) -> AExp {
+    match (temps.get(expr), expr) {
+        (Some(temp), _) => AExp::Variable(*temp),
+        (None, AExp::ArithmeticOp(lhs, op, rhs)) => AExp::ArithmeticOp(
+            Box::new(replace(lhs, temps)),
+            op.clone(),
+            Box::new(replace(rhs, temps)),
+        ),
+        (None, AExp::ArrayElement(array, index)) => {
+            AExp::ArrayElement(*array, Box::new(replace(index, temps)))
+        }
+        (None, _) => expr.clone(),
+    }
+}
+
+/// introduces a temporary for every expression that is recomputed while it is still available
+/// (according to `aea`), assigns it wherever the expression is computed anew and reuses it
+/// everywhere else
+pub fn eliminate(program: &Program, aea: &AEAnalysis) -> Program {
+    let mut reused: Vec<AExp> = (1..=program.len)
+        .flat_map(|label| {
+            computed(&program.at(label).unwrap())
+                .into_iter()
+                .filter(move |expr| aea.entry[&label].contains(expr))
+        })
+        .collect::<BTreeSet<AExp>>()
+        .into_iter()
+        .collect();
+    reused.sort_by_key(|expr| (expr.size(), expr.to_string()));
+
+    // there are always fresh variables left, so every reused expression gets a temporary
+    let temps: Temporaries = reused
+        .into_iter()
+        .zip(FreshVariables::new(program.free_vars()))
+        .collect();
+
+    program.with_contents(eliminate_stmt(program.contents.clone(), aea, &temps))
+}
+
+pub fn run(program: &Program) -> Result<Program, &'static str> {
+    supported(program)?;
+
+    let aea = algorithm::mfp::run(program, &AvailableExpressions::new(program));
+    Ok(eliminate(program, &aea))
+}
+
+/// the assignments to temporaries needed before the block at `label`, i.e. for all expressions
+/// it computes that are not available yet
+fn prelude(
+    block: &Block,
+    label: Label,
+    aea: &AEAnalysis,
+    temps: &Temporaries,
+) -> Option<Statement> {
+    let mut needed: Vec<AExp> = computed(block)
+        .into_iter()
+        .filter(|expr| temps.contains_key(expr) && !aea.entry[&label].contains(expr))
+        .collect();
+    needed.sort_by_key(|expr| (expr.size(), expr.to_string()));
+
+    needed
+        .into_iter()
+        .map(|expr| {
+            // the subexpressions are either available or have just been assigned
+            let value = match &expr {
+                AExp::ArithmeticOp(lhs, op, rhs) => AExp::ArithmeticOp(
+                    Box::new(replace(lhs, temps)),
+                    op.clone(),
+                    Box::new(replace(rhs, temps)),
+                ),
+                _ => expr.clone(),
+            };
+            Statement::Atom(Block::assignment(0, temps[&expr], value))
+        })
+        .fold(None, |acc, stmt| sequence(acc, Some(stmt)))
+}
+
+fn eliminate_test(test: TestBlock, temps: &Temporaries) -> TestBlock {
+    TestBlock {
+        label: test.label,
+        expr: test.expr.map_aexps(&|expr| replace(expr, temps)),
+    }
+}
+
+fn eliminate_stmt(stmt: Statement, aea: &AEAnalysis, temps: &Temporaries) -> Statement {
+    let with_prelude =
+        |prelude: Option<Statement>, stmt: Statement| sequence(prelude, Some(stmt)).unwrap();
+
+    match stmt {
+        Statement::Atom(block) => {
+            let prelude = prelude(&block, block.get_label(), aea, temps);
+            let block = match block {
+                Block::Assignment(AssignmentBlock { label, var, expr }) => {
+                    Block::assignment(label, var, replace(&expr, temps))
+                }
+                Block::ArrayAssignment(ArrayAssignmentBlock {
+                    label,
+                    array,
+                    index,
+                    expr,
+                }) => Block::array_assignment(
+                    label,
+                    array,
+                    replace(&index, temps),
+                    replace(&expr, temps),
+                ),
+                Block::Store(StoreBlock { label, ptr, expr }) => {
+                    Block::store(label, ptr, replace(&expr, temps))
+                }
+                Block::Write(WriteBlock { label, expr }) => {
+                    Block::write(label, replace(&expr, temps))
+                }
+                Block::Test(test) => Block::Test(eliminate_test(test, temps)),
+                Block::Skip(_)
+                | Block::Read(_)
+                | Block::Entry(_)
+                | Block::Exit(_)
+                | Block::Call(_)
+                | Block::Return(_) => block,
+            };
+
+            with_prelude(prelude, Statement::Atom(block))
+        }
+
+        Statement::Sequence(stmt1, stmt2) => Statement::Sequence(
+            Box::new(eliminate_stmt(*stmt1, aea, temps)),
+            Box::new(eliminate_stmt(*stmt2, aea, temps)),
+        ),
+
+        Statement::IfThenElse(test, stmt1, stmt2) => {
+            let prelude = prelude(&Block::Test(test.clone()), test.label, aea, temps);
+
+            with_prelude(
+                prelude,
+                Statement::IfThenElse(
+                    eliminate_test(test, temps),
+                    Box::new(eliminate_stmt(*stmt1, aea, temps)),
+                    Box::new(eliminate_stmt(*stmt2, aea, temps)),
+                ),
+            )
+        }
+
+        Statement::While(test, stmt1) => {
+            // the test is evaluated again after every iteration, so its temporaries need to be
+            // reassigned at the end of the body as well
+            let prelude = prelude(&Block::Test(test.clone()), test.label, aea, temps);
+            let body = sequence(Some(eliminate_stmt(*stmt1, aea, temps)), prelude.clone());
+
+            with_prelude(
+                prelude,
+                Statement::While(eliminate_test(test, temps), Box::new(body.unwrap())),
+            )
+        }
+
+        Statement::Call(call) => Statement::Call(call),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    /// compares the programs as printed, as the transformation may nest sequences differently
+    fn assert_eliminated(input: &str, expected: &str) {
+        let program = parser::parse(input).unwrap();
+        assert_eq!(
+            run(&program).unwrap().to_string(),
+            parser::parse(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn temporaries_are_inserted_and_reused() {
+        assert_eliminated(
+            "x := a + b; y := a + b; z := a * b; w := a * b + 1; v := a * b + 1 ",
+            "t2 := a + b; x := t2; y := t2; t1 := a * b; z := t1; \
+             t3 := t1 + 1; w := t3; v := t3 ",
+        );
+    }
+
+    #[test]
+    fn temporaries_are_reassigned_in_loops() {
+        assert_eliminated(
+            "x := a + b; while a + b < 10 do x := a + b; a := a + 1 enddo ",
+            "t1 := a + b; x := t1; t1 := a + b; \
+             while t1 < 10 do x := t1; a := a + 1; t1 := a + b enddo ",
+        );
+    }
+
+    #[test]
+    fn temporaries_do_not_collide_with_variables_of_the_program() {
+        assert_eliminated(
+            "t1 := a + b; y := a + b; t3 := a - b; z := a - b ",
+            "t2 := a + b; t1 := t2; y := t2; t4 := a - b; t3 := t4; z := t4 ",
+        );
+
+        let used = [Variable::new("t1"), Variable::new("t3")].into();
+        let fresh: Vec<Variable> = FreshVariables::new(used).take(3).collect();
+        assert_eq!(fresh, ["t2", "t4", "t5"].map(Variable::new));
+    }
+
+    #[test]
+    fn programs_with_nothing_to_eliminate_are_unchanged() {
+        assert_eliminated(
+            "x := a + b; a := 1; y := a + b ",
+            "x := a + b; a := 1; y := a + b ",
+        );
+    }
+}
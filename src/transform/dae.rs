@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+use crate::{
+    algorithm,
+    analysis::lv::{LVAnalysis, LiveVariables},
+    block::{ArrayAssignmentBlock, AssignmentBlock, Block},
+    program::Program,
+    statement::Statement,
+    transform::{or_skip, sequence, supported},
+};
+
+/// removes every assignment to a variable (or an element of an array) that is dead at its exit,
+/// according to `lva`
+pub fn eliminate(program: &Program, lva: &LVAnalysis) -> Program {
+    program.with_contents(or_skip(eliminate_stmt(program.contents.clone(), lva)))
+}
+
+/// repeatedly removes dead assignments until there are none left, as removing one assignment
+/// may cause the variables it uses to become dead as well
+pub fn run(program: &Program) -> Result<Program, &'static str> {
+    supported(program)?;
+
+    let mut program = program.clone();
+    loop {
+        let lva = algorithm::mfp::run(&program, &LiveVariables::new(&program));
+        let next = eliminate(&program, &lva);
+
+        if next == program {
+            return Ok(program);
+        }
+
+        program = next;
+    }
+}
+
+/// returns the statement without its dead assignments, or `None` if nothing remains of it
+fn eliminate_stmt(stmt: Statement, lva: &LVAnalysis) -> Option<Statement> {
+    match stmt {
+        Statement::Atom(
+            Block::Assignment(AssignmentBlock { label, var, .. })
+            | Block::ArrayAssignment(ArrayAssignmentBlock {
+                label, array: var, ..
+            }),
+        ) if !lva.exit[&label].contains(&var) => None,
+
+        // reads consume input, so they are kept even if the variable read is dead
+        Statement::Atom(block) => Some(Statement::Atom(block)),
+
+        Statement::Sequence(stmt1, stmt2) => {
+            sequence(eliminate_stmt(*stmt1, lva), eliminate_stmt(*stmt2, lva))
+        }
+
+        Statement::IfThenElse(test, stmt1, stmt2) => Some(Statement::IfThenElse(
+            test,
+            Box::new(or_skip(eliminate_stmt(*stmt1, lva))),
+            Box::new(or_skip(eliminate_stmt(*stmt2, lva))),
+        )),
+
+        Statement::While(test, stmt1) => Some(Statement::While(
+            test,
+            Box::new(or_skip(eliminate_stmt(*stmt1, lva))),
+        )),
+
+        Statement::Call(call) => Some(Statement::Call(call)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    /// compares the programs as printed, as the transformation may nest sequences differently
+    fn assert_eliminated(input: &str, expected: &str) {
+        let program = parser::parse(input).unwrap();
+        assert_eq!(
+            run(&program).unwrap().to_string(),
+            parser::parse(expected).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn assignments_dead_after_removing_others_are_removed() {
+        // x is only dead once the assignment to y has been removed
+        assert_eliminated("x := 1; y := x + 1; z := 2; write z ", "z := 2; write z ");
+        assert_eliminated("x := 1; x := 2; write x ", "x := 2; write x ");
+    }
+
+    #[test]
+    fn reads_are_kept() {
+        assert_eliminated("read x; y := 1; write y ", "read x; y := 1; write y ");
+    }
+
+    #[test]
+    fn assignments_in_loops() {
+        assert_eliminated(
+            "x := 0; while x < 10 do y := x; x := x + 1 enddo; write x ",
+            "x := 0; while x < 10 do x := x + 1 enddo; write x ",
+        );
+        assert_eliminated(
+            "x := 0; while x < 10 do y := x enddo ",
+            "x := 0; while x < 10 do skip enddo ",
+        );
+    }
+}